@@ -0,0 +1,74 @@
+//! Compares reading rows with `Row::get` against the consuming `into_typed` and `into_tuples`
+//!
+//! Run with `cargo bench --bench rows`.
+
+#[macro_use]
+extern crate criterion;
+#[macro_use]
+extern crate serde_derive;
+
+extern crate rusted_cypher;
+extern crate serde_json;
+
+use criterion::{BatchSize, Criterion};
+use rusted_cypher::cypher::CypherResult;
+
+const ROWS: usize = 10_000;
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+struct Person {
+    name: String,
+    age: u32,
+    tags: Vec<String>,
+}
+
+fn make_result() -> CypherResult {
+    let data = (0..ROWS)
+        .map(|i| serde_json::json!({
+            "row": [format!("Person {}", i), i, ["neo4j", "cypher", "rust"]]
+        }))
+        .collect::<Vec<_>>();
+
+    serde_json::from_value(serde_json::json!({
+        "columns": ["name", "age", "tags"],
+        "data": data,
+    })).unwrap()
+}
+
+// Each benchmark turns a result into typed values. The values are returned, so they are dropped
+// outside of the measurement, while the result itself is dropped inside of it since the consuming
+// methods free it row by row.
+fn rows(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rows");
+
+    group.bench_function("rows", |b| b.iter_batched(make_result, |result| {
+        let people = result.rows()
+            .map(|row| Person {
+                name: row.get("name").unwrap(),
+                age: row.get("age").unwrap(),
+                tags: row.get("tags").unwrap(),
+            })
+            .collect::<Vec<_>>();
+
+        drop(result);
+        people
+    }, BatchSize::LargeInput));
+
+    group.bench_function("into_typed", |b| b.iter_batched(make_result, |result| {
+        result.into_typed::<Person>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }, BatchSize::LargeInput));
+
+    group.bench_function("into_tuples", |b| b.iter_batched(make_result, |result| {
+        result.into_tuples::<(String, u32, Vec<String>)>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }, BatchSize::LargeInput));
+
+    group.finish();
+}
+
+criterion_group!(benches, rows);
+criterion_main!(benches);
@@ -0,0 +1,163 @@
+//! Derive macro for the `FromRow` trait of `rusted_cypher`
+//!
+//! Use it through the `derive` feature of `rusted_cypher`, which re-exports the macro.
+//!
+//! Each field of the struct is read from the column with the same name. The following attributes
+//! change how a field is read:
+//!
+//! - `#[cypher(rename = "n.name")]` reads the field from the given column
+//! - `#[cypher(default)]` uses `Default::default()` when the column is missing or `null`
+//! - `#[cypher(default = "path")]` calls the given function when the column is missing or `null`
+//! - `#[cypher(flatten)]` reads the field, which must implement `FromRow`, from the same row
+//!
+//! Fields of type `Option` are `None` when the column is missing or `null`.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Expr, ExprLit, Field, Fields, Lit, Type};
+
+#[proc_macro_derive(FromRow, attributes(cypher))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+enum DefaultValue {
+    None,
+    Trait,
+    Function(syn::Path),
+}
+
+struct FieldAttrs {
+    rename: Option<String>,
+    default: DefaultValue,
+    flatten: bool,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(Error::new_spanned(
+                &input.ident, "FromRow can only be derived for structs with named fields")),
+        },
+        _ => return Err(Error::new_spanned(
+            &input.ident, "FromRow can only be derived for structs")),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = fields.iter().map(expand_field).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(quote! {
+        impl #impl_generics ::rusted_cypher::cypher::result::FromRow for #name #ty_generics
+            #where_clause
+        {
+            fn from_row(row: &::rusted_cypher::cypher::result::Row)
+                -> ::std::result::Result<Self, ::rusted_cypher::GraphError>
+            {
+                ::std::result::Result::Ok(#name {
+                    #(#fields,)*
+                })
+            }
+        }
+    })
+}
+
+fn expand_field(field: &Field) -> Result<TokenStream2, Error> {
+    let attrs = parse_attrs(field)?;
+    let ident = field.ident.as_ref().expect("named field");
+    let ty = &field.ty;
+
+    if attrs.flatten {
+        return Ok(quote! {
+            #ident: <#ty as ::rusted_cypher::cypher::result::FromRow>::from_row(row)?
+        });
+    }
+
+    let column = attrs.rename.unwrap_or_else(|| unraw(ident));
+
+    let value = match attrs.default {
+        DefaultValue::Trait => quote! {
+            row.get_opt::<#ty>(#column)?.unwrap_or_default()
+        },
+        DefaultValue::Function(path) => quote! {
+            row.get_opt::<#ty>(#column)?.unwrap_or_else(#path)
+        },
+        DefaultValue::None if is_option(ty) => quote! {
+            row.get_opt::<#ty>(#column)?.unwrap_or(::std::option::Option::None)
+        },
+        DefaultValue::None => quote! {
+            row.get::<#ty>(#column)?
+        },
+    };
+
+    Ok(quote! { #ident: #value })
+}
+
+fn parse_attrs(field: &Field) -> Result<FieldAttrs, Error> {
+    let mut attrs = FieldAttrs {
+        rename: None,
+        default: DefaultValue::None,
+        flatten: false,
+    };
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("cypher")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                attrs.rename = Some(string_value(&meta.value()?.parse()?)?);
+            } else if meta.path.is_ident("default") {
+                attrs.default = if meta.input.peek(syn::Token![=]) {
+                    let path = string_value(&meta.value()?.parse()?)?;
+                    DefaultValue::Function(syn::parse_str(&path)?)
+                } else {
+                    DefaultValue::Trait
+                };
+            } else if meta.path.is_ident("flatten") {
+                attrs.flatten = true;
+            } else {
+                return Err(meta.error("unknown cypher attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let has_default = !matches!(attrs.default, DefaultValue::None);
+    if attrs.flatten && (attrs.rename.is_some() || has_default) {
+        return Err(Error::new_spanned(field,
+                                      "flatten cannot be combined with rename or default"));
+    }
+
+    Ok(attrs)
+}
+
+fn string_value(expr: &Expr) -> Result<String, Error> {
+    match *expr {
+        Expr::Lit(ExprLit { lit: Lit::Str(ref s), .. }) => Ok(s.value()),
+        _ => Err(Error::new_spanned(expr, "expected a string literal")),
+    }
+}
+
+/// Removes the `r#` prefix of raw identifiers, so `r#type` reads the column `type`
+fn unraw(ident: &syn::Ident) -> String {
+    let ident = ident.to_string();
+    ident.trim_start_matches("r#").to_owned()
+}
+
+fn is_option(ty: &Type) -> bool {
+    match *ty {
+        Type::Path(ref path) => path.qself.is_none()
+            && path.path.segments.last().is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
@@ -0,0 +1,318 @@
+//! Authentication of the requests sent to the server
+//!
+//! An `AuthProvider` adds the credentials to each request, so they do not have to be given in the
+//! URL passed to `GraphClient::connect`. When the server answers `401 Unauthorized`, the provider
+//! is asked to refresh its credentials and the request is sent once more.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use rusted_cypher::{GraphClient, GraphError};
+//! # use rusted_cypher::auth::BasicAuth;
+//! # fn main() { doctest().unwrap(); }
+//! # fn doctest() -> Result<(), GraphError> {
+//! let graph = GraphClient::connect_with_auth("http://localhost:7474/db/data",
+//!                                            BasicAuth::new("neo4j", "neo4j"))?;
+//!
+//! graph.exec("MATCH (n:AUTH) RETURN n")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Expiring tokens are fetched again by `RefreshingBearerAuth`:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use rusted_cypher::{GraphClient, GraphError};
+//! # use rusted_cypher::auth::{RefreshingBearerAuth, Token};
+//! # fn fetch_token() -> Result<String, GraphError> { Ok(String::new()) }
+//! # fn main() { doctest().unwrap(); }
+//! # fn doctest() -> Result<(), GraphError> {
+//! let auth = RefreshingBearerAuth::new(|| {
+//!     fetch_token().map(|token| Token::expiring(token, Duration::from_secs(3600)))
+//! });
+//!
+//! let graph = GraphClient::connect_with_auth("https://neo4j.example.com:7473", auth)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use hyper;
+use hyper::client::Response;
+use hyper::header::{Authorization, Basic, Bearer, Headers};
+use hyper::status::StatusCode;
+
+use error::GraphError;
+
+/// Provides the credentials of the requests sent to the server
+///
+/// Providers are shared by the clones of a `GraphClient` and by its transactions, possibly across
+/// threads.
+pub trait AuthProvider: Send + Sync {
+    /// Adds the credentials to the headers of a request
+    fn authenticate(&self, headers: &mut Headers) -> Result<(), GraphError>;
+
+    /// Renews the credentials after the server rejected them
+    ///
+    /// Returns whether the request should be sent again with the new credentials. The default
+    /// implementation has nothing to renew and returns `false`.
+    fn refresh(&self) -> Result<bool, GraphError> {
+        Ok(false)
+    }
+}
+
+/// HTTP Basic Authentication
+#[derive(Clone, Debug)]
+pub struct BasicAuth {
+    username: String,
+    password: String,
+}
+
+impl BasicAuth {
+    pub fn new<U: Into<String>, P: Into<String>>(username: U, password: P) -> Self {
+        BasicAuth {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+impl AuthProvider for BasicAuth {
+    fn authenticate(&self, headers: &mut Headers) -> Result<(), GraphError> {
+        headers.set(Authorization(Basic {
+            username: self.username.clone(),
+            password: Some(self.password.clone()),
+        }));
+        Ok(())
+    }
+}
+
+/// A bearer token that does not change, sent in the `Authorization` header
+#[derive(Clone, Debug)]
+pub struct BearerAuth {
+    token: String,
+}
+
+impl BearerAuth {
+    pub fn new<T: Into<String>>(token: T) -> Self {
+        BearerAuth { token: token.into() }
+    }
+}
+
+impl AuthProvider for BearerAuth {
+    fn authenticate(&self, headers: &mut Headers) -> Result<(), GraphError> {
+        headers.set(Authorization(Bearer { token: self.token.clone() }));
+        Ok(())
+    }
+}
+
+/// Credentials sent in a header of their own, as expected by some proxies in front of the server
+#[derive(Clone, Debug)]
+pub struct HeaderAuth {
+    name: Cow<'static, str>,
+    value: String,
+}
+
+impl HeaderAuth {
+    pub fn new<N, V>(name: N, value: V) -> Self
+        where N: Into<Cow<'static, str>>, V: Into<String>
+    {
+        HeaderAuth {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+impl AuthProvider for HeaderAuth {
+    fn authenticate(&self, headers: &mut Headers) -> Result<(), GraphError> {
+        headers.set_raw(self.name.clone(), vec![self.value.clone().into_bytes()]);
+        Ok(())
+    }
+}
+
+/// A bearer token returned by the function of a `RefreshingBearerAuth`
+#[derive(Clone, Debug)]
+pub struct Token {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
+impl Token {
+    /// Creates a token that is used until the server rejects it
+    pub fn new<T: Into<String>>(value: T) -> Self {
+        Token {
+            value: value.into(),
+            expires_at: None,
+        }
+    }
+
+    /// Creates a token that is fetched again once the given time has passed
+    pub fn expiring<T: Into<String>>(value: T, expires_in: Duration) -> Self {
+        Token {
+            value: value.into(),
+            expires_at: Some(Instant::now() + expires_in),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= Instant::now())
+    }
+}
+
+/// A bearer token fetched by the given function, again when it expires or is rejected by the
+/// server
+pub struct RefreshingBearerAuth<F> {
+    fetch: F,
+    token: Mutex<Option<Token>>,
+}
+
+impl<F> RefreshingBearerAuth<F>
+    where F: Fn() -> Result<Token, GraphError> + Send + Sync
+{
+    /// Creates the provider, the token being fetched when the first request is sent
+    pub fn new(fetch: F) -> Self {
+        RefreshingBearerAuth {
+            fetch,
+            token: Mutex::new(None),
+        }
+    }
+
+    fn token(&self) -> Result<String, GraphError> {
+        let mut token = self.token.lock()
+            .map_err(|_| GraphError::Other("Token lock poisoned".to_owned()))?;
+
+        match *token {
+            Some(ref token) if !token.is_expired() => return Ok(token.value.clone()),
+            _ => debug!("Fetching a new bearer token"),
+        }
+
+        let fetched = (self.fetch)()?;
+        let value = fetched.value.clone();
+        *token = Some(fetched);
+
+        Ok(value)
+    }
+}
+
+impl<F> AuthProvider for RefreshingBearerAuth<F>
+    where F: Fn() -> Result<Token, GraphError> + Send + Sync
+{
+    fn authenticate(&self, headers: &mut Headers) -> Result<(), GraphError> {
+        headers.set(Authorization(Bearer { token: self.token()? }));
+        Ok(())
+    }
+
+    fn refresh(&self) -> Result<bool, GraphError> {
+        let mut token = self.token.lock()
+            .map_err(|_| GraphError::Other("Token lock poisoned".to_owned()))?;
+        *token = None;
+        Ok(true)
+    }
+}
+
+/// Sends a request with the credentials of the provider, sending it again with renewed
+/// credentials if the server answers `401 Unauthorized`
+///
+/// The request is built by `send` from the headers to use.
+pub(crate) fn send<F>(auth: Option<&dyn AuthProvider>, headers: &Headers, send: F)
+    -> Result<Response, GraphError>
+    where F: Fn(Headers) -> hyper::Result<Response>
+{
+    let auth = match auth {
+        Some(auth) => auth,
+        None => return send(headers.clone()).map_err(From::from),
+    };
+
+    let authenticated = || -> Result<Headers, GraphError> {
+        let mut headers = headers.clone();
+        auth.authenticate(&mut headers)?;
+        Ok(headers)
+    };
+
+    let mut res = send(authenticated()?)?;
+
+    if res.status == StatusCode::Unauthorized && auth.refresh()? {
+        debug!("Sending the request again with renewed credentials");
+        // the body is read for the connection to go back to the pool
+        io::copy(&mut res, &mut io::sink())?;
+        res = send(authenticated()?)?;
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use hyper::header::{Authorization, Bearer};
+    use serde_json::json;
+    use super::*;
+    use ::GraphClient;
+    use ::testing::{FakeResponse, FakeServer};
+
+    #[test]
+    fn authenticate() {
+        let mut headers = Headers::new();
+
+        BasicAuth::new("neo4j", "secret").authenticate(&mut headers).unwrap();
+        assert_eq!(headers.get_raw("Authorization").unwrap()[0], b"Basic bmVvNGo6c2VjcmV0");
+
+        BearerAuth::new("token").authenticate(&mut headers).unwrap();
+        assert_eq!(headers.get_raw("Authorization").unwrap()[0], b"Bearer token");
+
+        HeaderAuth::new("X-Api-Key", "key").authenticate(&mut headers).unwrap();
+        assert_eq!(headers.get_raw("X-Api-Key").unwrap()[0], b"key");
+    }
+
+    #[test]
+    fn refresh_expired_token() {
+        let fetched = AtomicUsize::new(0);
+        let auth = RefreshingBearerAuth::new(|| {
+            let n = fetched.fetch_add(1, Ordering::SeqCst);
+            Ok(Token::expiring(n.to_string(), Duration::from_secs(if n == 0 { 0 } else { 60 })))
+        });
+
+        let mut headers = Headers::new();
+        auth.authenticate(&mut headers).unwrap();
+        auth.authenticate(&mut headers).unwrap();
+        auth.authenticate(&mut headers).unwrap();
+
+        assert_eq!(headers.get::<Authorization<Bearer>>().unwrap().token, "1");
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn refresh_rejected_token() {
+        // the server only accepts the second token
+        let server = FakeServer::start().unwrap();
+        server.require_header("Authorization", "Bearer token-1");
+        server.respond("RETURN 1", FakeResponse::rows(&["n"], vec![vec![json!(1)]]));
+
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let auth = {
+            let fetched = fetched.clone();
+            RefreshingBearerAuth::new(move || {
+                Ok(Token::new(format!("token-{}", fetched.fetch_add(1, Ordering::SeqCst))))
+            })
+        };
+
+        let graph = GraphClient::connect_with_auth(server.url(), auth).unwrap();
+        let result = graph.exec("RETURN 1").unwrap();
+        assert_eq!(result.rows().next().unwrap().get::<i32>("n").unwrap(), 1);
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+
+        let static_token = GraphClient::connect_with_auth(server.url(), BearerAuth::new("token-0"))
+            .and_then(|graph| graph.exec("RETURN 1"));
+        assert!(matches!(static_token, Err(GraphError::Neo4j(_))));
+    }
+}
@@ -0,0 +1,670 @@
+//! Typed builder of cypher statements
+//!
+//! A `Query` is built clause by clause and rendered into a `Statement`. Values given to the
+//! builder, like property values and operands of conditions, are never written into the statement
+//! text: each one becomes a parameter named `p0`, `p1` and so on. Labels, relationship types and
+//! property keys are quoted with backticks when they are not plain identifiers.
+//!
+//! Expressions, like the items of `RETURN`, are written as they are given.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rusted_cypher::{GraphError, Statement};
+//! # use rusted_cypher::cypher::builder::{Condition, Node, Order, Pattern, Query, Rel};
+//! # fn main() { doctest().unwrap(); }
+//! # fn doctest() -> Result<(), GraphError> {
+//! let statement: Statement = Query::new()
+//!     .match_(Pattern::new(Node::new("a").label("LANG").property("name", "Rust")?)
+//!         .outgoing(Rel::new("r").rel_type("INFLUENCED_BY"), Node::new("b")))
+//!     .where_(Condition::gt("b.year", 1980)?.and(Condition::is_not_null("b.name")))
+//!     .return_(&["b.name", "b.year"])
+//!     .order_by("b.year", Order::Desc)
+//!     .limit(10)
+//!     .into();
+//!
+//! assert_eq!(statement.statement(),
+//!            "MATCH (a:LANG {name: $p0})-[r:INFLUENCED_BY]->(b) \
+//!             WHERE (b.year > $p1 AND b.name IS NOT NULL) \
+//!             RETURN b.name, b.year ORDER BY b.year DESC LIMIT 10");
+//! assert_eq!(statement.param::<String>("p0").unwrap()?, "Rust");
+//! assert_eq!(statement.param::<u32>("p1").unwrap()?, 1980);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use serde::Serialize;
+use serde_json::{self, Value};
+use serde_json::error::Error as JsonError;
+
+use super::Statement;
+
+/// Statement text interleaved with the values of its parameters
+#[derive(Clone, Debug, Default)]
+struct Fragment {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    Text(String),
+    Param(Value),
+}
+
+impl Fragment {
+    fn text(&mut self, text: &str) {
+        match self.parts.last_mut() {
+            Some(Part::Text(last)) => last.push_str(text),
+            _ => self.parts.push(Part::Text(text.to_owned())),
+        }
+    }
+
+    fn param<V: Serialize>(&mut self, value: V) -> Result<(), JsonError> {
+        self.parts.push(Part::Param(serde_json::to_value(value)?));
+        Ok(())
+    }
+
+    fn append(&mut self, other: Fragment) {
+        for part in other.parts {
+            match part {
+                Part::Text(text) => self.text(&text),
+                param => self.parts.push(param),
+            }
+        }
+    }
+
+    fn join<'a, I: IntoIterator<Item = &'a str>>(&mut self, items: I) {
+        for (index, item) in items.into_iter().enumerate() {
+            if index > 0 {
+                self.text(", ");
+            }
+            self.text(item);
+        }
+    }
+}
+
+/// Quotes a name with backticks unless it is a plain identifier
+fn escape(name: &str) -> Cow<'_, str> {
+    let mut chars = name.chars();
+    let plain = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if plain {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("`{}`", name.replace('`', "``")))
+    }
+}
+
+/// Renders properties as a map literal, as in `{name: $p0}`
+fn properties(fragment: &mut Fragment, properties: &[(String, Value)]) {
+    if properties.is_empty() {
+        return;
+    }
+
+    fragment.text(" {");
+    for (index, (key, value)) in properties.iter().enumerate() {
+        if index > 0 {
+            fragment.text(", ");
+        }
+        fragment.text(&escape(key));
+        fragment.text(": ");
+        fragment.parts.push(Part::Param(value.clone()));
+    }
+    fragment.text("}");
+}
+
+/// A node of a pattern, as in `(n:LANG {name: $p0})`
+#[derive(Clone, Debug, Default)]
+pub struct Node {
+    variable: Option<String>,
+    labels: Vec<String>,
+    properties: Vec<(String, Value)>,
+}
+
+impl Node {
+    /// Creates a node bound to the given variable
+    pub fn new<T: Into<String>>(variable: T) -> Self {
+        Node {
+            variable: Some(variable.into()),
+            ..Node::default()
+        }
+    }
+
+    /// Creates a node that is not bound to a variable
+    pub fn anonymous() -> Self {
+        Node::default()
+    }
+
+    pub fn label<T: Into<String>>(mut self, label: T) -> Self {
+        self.labels.push(label.into());
+        self
+    }
+
+    /// Adds a property the node must have, whose value is sent as a parameter
+    pub fn property<K, V>(mut self, key: K, value: V) -> Result<Self, JsonError>
+        where K: Into<String>, V: Serialize
+    {
+        self.properties.push((key.into(), serde_json::to_value(value)?));
+        Ok(self)
+    }
+
+    fn render(&self, fragment: &mut Fragment) {
+        fragment.text("(");
+        if let Some(ref variable) = self.variable {
+            fragment.text(&escape(variable));
+        }
+        for label in &self.labels {
+            fragment.text(":");
+            fragment.text(&escape(label));
+        }
+        properties(fragment, &self.properties);
+        fragment.text(")");
+    }
+}
+
+/// A relationship of a pattern, as in `[r:KNOWS {since: $p0}]`
+#[derive(Clone, Debug, Default)]
+pub struct Rel {
+    variable: Option<String>,
+    types: Vec<String>,
+    properties: Vec<(String, Value)>,
+}
+
+impl Rel {
+    /// Creates a relationship bound to the given variable
+    pub fn new<T: Into<String>>(variable: T) -> Self {
+        Rel {
+            variable: Some(variable.into()),
+            ..Rel::default()
+        }
+    }
+
+    /// Creates a relationship that is not bound to a variable
+    pub fn anonymous() -> Self {
+        Rel::default()
+    }
+
+    /// Adds a type the relationship may have
+    ///
+    /// A relationship with several types matches any of them.
+    pub fn rel_type<T: Into<String>>(mut self, rel_type: T) -> Self {
+        self.types.push(rel_type.into());
+        self
+    }
+
+    /// Adds a property the relationship must have, whose value is sent as a parameter
+    pub fn property<K, V>(mut self, key: K, value: V) -> Result<Self, JsonError>
+        where K: Into<String>, V: Serialize
+    {
+        self.properties.push((key.into(), serde_json::to_value(value)?));
+        Ok(self)
+    }
+
+    fn render(&self, fragment: &mut Fragment) {
+        let empty = self.variable.is_none() && self.types.is_empty() && self.properties.is_empty();
+        if empty {
+            return;
+        }
+
+        fragment.text("[");
+        if let Some(ref variable) = self.variable {
+            fragment.text(&escape(variable));
+        }
+        for (index, rel_type) in self.types.iter().enumerate() {
+            fragment.text(if index == 0 { ":" } else { "|" });
+            fragment.text(&escape(rel_type));
+        }
+        properties(fragment, &self.properties);
+        fragment.text("]");
+    }
+}
+
+/// Direction of a relationship in a pattern
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Outgoing,
+    Incoming,
+    Both,
+}
+
+/// A path pattern, made of nodes connected by relationships
+///
+/// A pattern can also be created from a string, which is written as it is.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    fragment: Fragment,
+}
+
+impl Pattern {
+    /// Creates a pattern starting at the given node
+    pub fn new(node: Node) -> Self {
+        let mut fragment = Fragment::default();
+        node.render(&mut fragment);
+        Pattern { fragment }
+    }
+
+    /// Continues the pattern with `-[rel]->(node)`
+    pub fn outgoing(self, rel: Rel, node: Node) -> Self {
+        self.rel(Direction::Outgoing, rel, node)
+    }
+
+    /// Continues the pattern with `<-[rel]-(node)`
+    pub fn incoming(self, rel: Rel, node: Node) -> Self {
+        self.rel(Direction::Incoming, rel, node)
+    }
+
+    /// Continues the pattern with `-[rel]-(node)`, which matches both directions
+    pub fn related(self, rel: Rel, node: Node) -> Self {
+        self.rel(Direction::Both, rel, node)
+    }
+
+    fn rel(mut self, direction: Direction, rel: Rel, node: Node) -> Self {
+        let fragment = &mut self.fragment;
+        fragment.text(if direction == Direction::Incoming { "<-" } else { "-" });
+        rel.render(fragment);
+        fragment.text(if direction == Direction::Outgoing { "->" } else { "-" });
+        node.render(fragment);
+        self
+    }
+}
+
+impl From<Node> for Pattern {
+    fn from(node: Node) -> Self {
+        Pattern::new(node)
+    }
+}
+
+impl<'a> From<&'a str> for Pattern {
+    fn from(pattern: &'a str) -> Self {
+        let mut fragment = Fragment::default();
+        fragment.text(pattern);
+        Pattern { fragment }
+    }
+}
+
+/// A condition of a `WHERE` clause
+///
+/// The operand of a comparison is sent as a parameter, while the expression it is compared to is
+/// written as it is.
+#[derive(Clone, Debug)]
+pub struct Condition {
+    fragment: Fragment,
+}
+
+impl Condition {
+    /// Creates a condition from an expression, which is written as it is
+    pub fn raw<T: AsRef<str>>(expression: T) -> Self {
+        let mut fragment = Fragment::default();
+        fragment.text(expression.as_ref());
+        Condition { fragment }
+    }
+
+    fn compare<V: Serialize>(expression: &str, operator: &str, value: V)
+        -> Result<Self, JsonError>
+    {
+        let mut condition = Condition::raw(expression);
+        condition.fragment.text(operator);
+        condition.fragment.param(value)?;
+        Ok(condition)
+    }
+
+    /// `expression = value`
+    pub fn eq<V: Serialize>(expression: &str, value: V) -> Result<Self, JsonError> {
+        Condition::compare(expression, " = ", value)
+    }
+
+    /// `expression <> value`
+    pub fn ne<V: Serialize>(expression: &str, value: V) -> Result<Self, JsonError> {
+        Condition::compare(expression, " <> ", value)
+    }
+
+    /// `expression < value`
+    pub fn lt<V: Serialize>(expression: &str, value: V) -> Result<Self, JsonError> {
+        Condition::compare(expression, " < ", value)
+    }
+
+    /// `expression <= value`
+    pub fn le<V: Serialize>(expression: &str, value: V) -> Result<Self, JsonError> {
+        Condition::compare(expression, " <= ", value)
+    }
+
+    /// `expression > value`
+    pub fn gt<V: Serialize>(expression: &str, value: V) -> Result<Self, JsonError> {
+        Condition::compare(expression, " > ", value)
+    }
+
+    /// `expression >= value`
+    pub fn ge<V: Serialize>(expression: &str, value: V) -> Result<Self, JsonError> {
+        Condition::compare(expression, " >= ", value)
+    }
+
+    /// `expression IN values`
+    pub fn is_in<V: Serialize>(expression: &str, values: V) -> Result<Self, JsonError> {
+        Condition::compare(expression, " IN ", values)
+    }
+
+    /// `expression STARTS WITH value`
+    pub fn starts_with(expression: &str, value: &str) -> Result<Self, JsonError> {
+        Condition::compare(expression, " STARTS WITH ", value)
+    }
+
+    /// `expression ENDS WITH value`
+    pub fn ends_with(expression: &str, value: &str) -> Result<Self, JsonError> {
+        Condition::compare(expression, " ENDS WITH ", value)
+    }
+
+    /// `expression CONTAINS value`
+    pub fn contains(expression: &str, value: &str) -> Result<Self, JsonError> {
+        Condition::compare(expression, " CONTAINS ", value)
+    }
+
+    /// `expression IS NULL`
+    pub fn is_null(expression: &str) -> Self {
+        Condition::raw(format!("{} IS NULL", expression))
+    }
+
+    /// `expression IS NOT NULL`
+    pub fn is_not_null(expression: &str) -> Self {
+        Condition::raw(format!("{} IS NOT NULL", expression))
+    }
+
+    /// `(self AND other)`
+    pub fn and(self, other: Condition) -> Self {
+        self.combine(" AND ", other)
+    }
+
+    /// `(self OR other)`
+    pub fn or(self, other: Condition) -> Self {
+        self.combine(" OR ", other)
+    }
+
+    /// `NOT (self)`
+    pub fn negate(self) -> Self {
+        let mut fragment = Fragment::default();
+        fragment.text("NOT (");
+        fragment.append(self.fragment);
+        fragment.text(")");
+        Condition { fragment }
+    }
+
+    fn combine(self, operator: &str, other: Condition) -> Self {
+        let mut fragment = Fragment::default();
+        fragment.text("(");
+        fragment.append(self.fragment);
+        fragment.text(operator);
+        fragment.append(other.fragment);
+        fragment.text(")");
+        Condition { fragment }
+    }
+}
+
+/// Direction of an `ORDER BY` item
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+struct Clause {
+    keyword: &'static str,
+    body: Fragment,
+}
+
+/// Keywords whose consecutive clauses are merged into one, separating their items with commas
+const MERGED_KEYWORDS: &[&str] = &["SET", "ON CREATE SET", "ON MATCH SET", "ORDER BY"];
+
+/// A cypher query built clause by clause
+///
+/// Clauses are rendered in the order they are added, and the query is converted into a
+/// `Statement` with `build` or `Into<Statement>`.
+#[derive(Default)]
+pub struct Query {
+    clauses: Vec<Clause>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Query::default()
+    }
+
+    fn clause(mut self, keyword: &'static str, body: Fragment) -> Self {
+        if let Some(last) = self.clauses.last_mut() {
+            if last.keyword == keyword && MERGED_KEYWORDS.contains(&keyword) {
+                last.body.text(", ");
+                last.body.append(body);
+                return self;
+            }
+        }
+
+        self.clauses.push(Clause { keyword, body });
+        self
+    }
+
+    fn items(self, keyword: &'static str, items: &[&str]) -> Self {
+        let mut body = Fragment::default();
+        body.join(items.iter().cloned());
+        self.clause(keyword, body)
+    }
+
+    fn assignment<V: Serialize>(self, keyword: &'static str, property: &str, value: V)
+        -> Result<Self, JsonError>
+    {
+        let mut body = Fragment::default();
+        body.text(property);
+        body.text(" = ");
+        body.param(value)?;
+        Ok(self.clause(keyword, body))
+    }
+
+    /// `MATCH pattern`
+    pub fn match_<P: Into<Pattern>>(self, pattern: P) -> Self {
+        self.clause("MATCH", pattern.into().fragment)
+    }
+
+    /// `OPTIONAL MATCH pattern`
+    pub fn optional_match<P: Into<Pattern>>(self, pattern: P) -> Self {
+        self.clause("OPTIONAL MATCH", pattern.into().fragment)
+    }
+
+    /// `WHERE condition`
+    ///
+    /// Conditions are combined with `Condition::and` and `Condition::or`, a query needs a single
+    /// `WHERE` after each `MATCH` or `WITH`.
+    pub fn where_(self, condition: Condition) -> Self {
+        self.clause("WHERE", condition.fragment)
+    }
+
+    /// `CREATE pattern`
+    pub fn create<P: Into<Pattern>>(self, pattern: P) -> Self {
+        self.clause("CREATE", pattern.into().fragment)
+    }
+
+    /// `MERGE pattern`, followed by `on_create_set` and `on_match_set`
+    pub fn merge<P: Into<Pattern>>(self, pattern: P) -> Self {
+        self.clause("MERGE", pattern.into().fragment)
+    }
+
+    /// `ON CREATE SET property = value`, after a `MERGE`
+    pub fn on_create_set<V: Serialize>(self, property: &str, value: V)
+        -> Result<Self, JsonError>
+    {
+        self.assignment("ON CREATE SET", property, value)
+    }
+
+    /// `ON MATCH SET property = value`, after a `MERGE`
+    pub fn on_match_set<V: Serialize>(self, property: &str, value: V)
+        -> Result<Self, JsonError>
+    {
+        self.assignment("ON MATCH SET", property, value)
+    }
+
+    /// `SET property = value`
+    pub fn set<V: Serialize>(self, property: &str, value: V) -> Result<Self, JsonError> {
+        self.assignment("SET", property, value)
+    }
+
+    /// `DELETE variables`
+    pub fn delete(self, variables: &[&str]) -> Self {
+        self.items("DELETE", variables)
+    }
+
+    /// `DETACH DELETE variables`
+    pub fn detach_delete(self, variables: &[&str]) -> Self {
+        self.items("DETACH DELETE", variables)
+    }
+
+    /// `WITH items`
+    pub fn with(self, items: &[&str]) -> Self {
+        self.items("WITH", items)
+    }
+
+    /// `UNWIND values AS variable`
+    pub fn unwind<V: Serialize>(self, values: V, variable: &str) -> Result<Self, JsonError> {
+        let mut body = Fragment::default();
+        body.param(values)?;
+        body.text(" AS ");
+        body.text(&escape(variable));
+        Ok(self.clause("UNWIND", body))
+    }
+
+    /// `RETURN items`
+    pub fn return_(self, items: &[&str]) -> Self {
+        self.items("RETURN", items)
+    }
+
+    /// `RETURN DISTINCT items`
+    pub fn return_distinct(self, items: &[&str]) -> Self {
+        self.items("RETURN DISTINCT", items)
+    }
+
+    /// `ORDER BY expression`, adding to the previous `ORDER BY` if it is the last clause
+    pub fn order_by(self, expression: &str, order: Order) -> Self {
+        let mut body = Fragment::default();
+        body.text(expression);
+        if order == Order::Desc {
+            body.text(" DESC");
+        }
+        self.clause("ORDER BY", body)
+    }
+
+    /// `SKIP count`
+    pub fn skip(self, count: u64) -> Self {
+        self.items("SKIP", &[&count.to_string()])
+    }
+
+    /// `LIMIT count`
+    pub fn limit(self, count: u64) -> Self {
+        self.items("LIMIT", &[&count.to_string()])
+    }
+
+    /// Renders the query into a `Statement`, with a parameter for each value
+    pub fn build(self) -> Statement {
+        let mut text = String::new();
+        let mut params = Vec::new();
+
+        for (index, clause) in self.clauses.into_iter().enumerate() {
+            if index > 0 {
+                text.push(' ');
+            }
+            text.push_str(clause.keyword);
+            text.push(' ');
+
+            for part in clause.body.parts {
+                match part {
+                    Part::Text(part) => text.push_str(&part),
+                    Part::Param(value) => {
+                        let name = format!("p{}", params.len());
+                        text.push('$');
+                        text.push_str(&name);
+                        params.push((name, value));
+                    }
+                }
+            }
+        }
+
+        let mut statement = Statement::new(text);
+        for (name, value) in params {
+            statement.add_param(name, &value)
+                .expect("a serde_json::Value always serializes");
+        }
+
+        statement
+    }
+}
+
+impl From<Query> for Statement {
+    fn from(query: Query) -> Self {
+        query.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_and_set() {
+        let statement = Query::new()
+            .merge(Node::new("n").label("LANG").property("name", "Rust").unwrap())
+            .on_create_set("n.created", 2010).unwrap()
+            .on_create_set("n.safe", true).unwrap()
+            .on_match_set("n.seen", 1).unwrap()
+            .set("n.level", "low").unwrap()
+            .return_(&["n"])
+            .build();
+
+        assert_eq!(statement.statement(),
+                   "MERGE (n:LANG {name: $p0}) ON CREATE SET n.created = $p1, n.safe = $p2 \
+                    ON MATCH SET n.seen = $p3 SET n.level = $p4 RETURN n");
+        assert!(statement.param::<bool>("p2").unwrap().unwrap());
+        assert_eq!(statement.param::<String>("p4").unwrap().unwrap(), "low");
+        assert!(statement.check_params().is_ok());
+    }
+
+    #[test]
+    fn patterns() {
+        let pattern = Pattern::new(Node::new("a"))
+            .incoming(Rel::anonymous().rel_type("KNOWS").rel_type("LIKES"), Node::anonymous())
+            .related(Rel::anonymous(), Node::new("my var").label("Odd`Label"))
+            .outgoing(Rel::new("r").property("since", 2000).unwrap(), Node::new("b"));
+
+        let statement = Query::new()
+            .optional_match(pattern)
+            .create("(a)-[:NEW]->(b)")
+            .detach_delete(&["r"])
+            .build();
+
+        assert_eq!(statement.statement(),
+                   "OPTIONAL MATCH (a)<-[:KNOWS|LIKES]-()--(`my var`:`Odd``Label`)\
+                    -[r {since: $p0}]->(b) CREATE (a)-[:NEW]->(b) DETACH DELETE r");
+    }
+
+    #[test]
+    fn conditions() {
+        let condition = Condition::eq("n.name", "Rust").unwrap()
+            .or(Condition::is_in("n.name", ["C", "C++"]).unwrap()
+                .and(Condition::starts_with("n.level", "lo").unwrap().negate()));
+
+        let statement = Query::new()
+            .unwind(vec![1, 2, 3], "x").unwrap()
+            .match_(Node::new("n"))
+            .where_(condition)
+            .with(&["n", "x"])
+            .return_distinct(&["n.name AS name"])
+            .order_by("name", Order::Asc)
+            .order_by("x", Order::Desc)
+            .skip(5)
+            .limit(10)
+            .build();
+
+        assert_eq!(statement.statement(),
+                   "UNWIND $p0 AS x MATCH (n) \
+                    WHERE (n.name = $p1 OR (n.name IN $p2 AND NOT (n.level STARTS WITH $p3))) \
+                    WITH n, x RETURN DISTINCT n.name AS name ORDER BY name, x DESC \
+                    SKIP 5 LIMIT 10");
+        assert_eq!(statement.param::<Vec<String>>("p2").unwrap().unwrap(), ["C", "C++"]);
+        assert_eq!(statement.parameters().len(), 4);
+    }
+}
@@ -0,0 +1,215 @@
+//! Deserialization of owned rows
+//!
+//! `RowDeserializer` feeds the values of a row to serde without copying them, either as a map from
+//! column names to values or as a sequence of values in column order.
+
+use std::vec;
+use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess,
+                Visitor};
+use serde::de::value::StrDeserializer;
+use serde::forward_to_deserialize_any;
+use serde_json::{Error, Value};
+
+/// How a row is presented to types that accept anything, like `serde_json::Value`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RowShape {
+    Map,
+    Seq,
+}
+
+pub(crate) struct RowDeserializer<'a> {
+    columns: &'a [String],
+    values: Vec<Value>,
+    shape: RowShape,
+}
+
+impl<'a> RowDeserializer<'a> {
+    pub(crate) fn new(columns: &'a [String], values: Vec<Value>, shape: RowShape) -> Self {
+        RowDeserializer {
+            columns,
+            values,
+            shape,
+        }
+    }
+
+    fn into_map(self) -> RowMap<'a> {
+        RowMap {
+            columns: self.columns.iter(),
+            values: self.values.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de, 'a> Deserializer<'de> for RowDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.shape {
+            RowShape::Map => visitor.visit_map(self.into_map()),
+            RowShape::Seq => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(self.into_map())
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str,
+                                           _fields: &'static [&'static str], visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_map(self.into_map())
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let len = self.values.len();
+        let mut seq = RowSeq { values: self.values.into_iter() };
+        let value = visitor.visit_seq(&mut seq)?;
+
+        match seq.values.len() {
+            0 => Ok(value),
+            remaining => Err(de::Error::invalid_length(len, &format!(
+                "a row of {} columns", len - remaining).as_str())),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize,
+                                                 visitor: V) -> Result<V::Value, Error>
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    /// A single column row can be deserialized into the type of its column
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct enum identifier ignored_any
+    }
+}
+
+struct RowMap<'a> {
+    columns: ::std::slice::Iter<'a, String>,
+    values: vec::IntoIter<Value>,
+    value: Option<Value>,
+}
+
+impl<'de, 'a> MapAccess<'de> for RowMap<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+        where K: DeserializeSeed<'de>
+    {
+        match (self.columns.next(), self.values.next()) {
+            (Some(column), Some(value)) => {
+                self.value = Some(value);
+                let key: StrDeserializer<Error> = column.as_str().into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+struct RowSeq {
+    values: vec::IntoIter<Value>,
+}
+
+impl<'de> SeqAccess<'de> for RowSeq {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+        where T: DeserializeSeed<'de>
+    {
+        match self.values.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::Deserialize;
+    use serde_json::json;
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Language {
+        name: String,
+        year: u32,
+        #[serde(default)]
+        safe: bool,
+    }
+
+    fn columns() -> Vec<String> {
+        vec!["name".to_owned(), "year".to_owned()]
+    }
+
+    fn values() -> Vec<Value> {
+        vec![json!("Rust"), json!(2010)]
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let columns = columns();
+        let language = Language::deserialize(
+            RowDeserializer::new(&columns, values(), RowShape::Map)).unwrap();
+
+        assert_eq!(language, Language { name: "Rust".to_owned(), year: 2010, safe: false });
+    }
+
+    #[test]
+    fn deserialize_tuple() {
+        let columns = columns();
+        let (name, year) = <(String, u32)>::deserialize(
+            RowDeserializer::new(&columns, values(), RowShape::Map)).unwrap();
+
+        assert_eq!(name, "Rust");
+        assert_eq!(year, 2010);
+
+        let result = <(String,)>::deserialize(
+            RowDeserializer::new(&columns, values(), RowShape::Map));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn deserialize_any() {
+        let columns = columns();
+
+        let map = BTreeMap::<String, Value>::deserialize(
+            RowDeserializer::new(&columns, values(), RowShape::Map)).unwrap();
+        assert_eq!(map["name"], "Rust");
+
+        let value = Value::deserialize(
+            RowDeserializer::new(&columns, values(), RowShape::Seq)).unwrap();
+        assert_eq!(value, json!(["Rust", 2010]));
+    }
+}
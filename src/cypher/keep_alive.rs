@@ -0,0 +1,165 @@
+//! Background thread resetting the timeout of an idle transaction before it expires
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use hyper::Client;
+use hyper::header::Headers;
+use time::{self, Tm};
+
+use ::auth::AuthProvider;
+use ::cassette::Cassette;
+use ::error::GraphError;
+use super::transaction::{TransactionResult, DATETIME_RFC822};
+
+/// The request resetting the timeout of a transaction, sent with an empty list of statements
+pub(crate) struct ResetRequest {
+    pub client: Arc<Client>,
+    pub headers: Headers,
+    pub auth: Option<Arc<dyn AuthProvider>>,
+    pub cassette: Option<Arc<Cassette>>,
+    pub transaction: String,
+}
+
+impl ResetRequest {
+    fn send(&self) -> Result<Tm, GraphError> {
+        reset_timeout(&self.client,
+                      &self.transaction,
+                      &self.headers,
+                      self.auth.as_deref(),
+                      self.cassette.as_deref())
+    }
+}
+
+/// Resets the timeout of the transaction, returning its new expiration time
+pub(crate) fn reset_timeout(client: &Client, transaction: &str, headers: &Headers,
+                            auth: Option<&dyn AuthProvider>, cassette: Option<&Cassette>)
+    -> Result<Tm, GraphError>
+{
+    let mut res = super::send_query(client, transaction, headers, auth, cassette, &[])?;
+
+    let result: TransactionResult = super::parse_response(&mut res)?;
+    time::strptime(&result.transaction.expires, DATETIME_RFC822).map_err(From::from)
+}
+
+pub(crate) struct State {
+    pub expires: Tm,
+    stopped: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    stopped: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Keeps a transaction alive until it is stopped or dropped
+///
+/// The transaction holds the lock returned by `KeepAlive::pause` while it sends a request, as the
+/// server rejects concurrent requests to the same transaction.
+pub(crate) struct KeepAlive {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl KeepAlive {
+    /// Starts resetting the timeout of the transaction once less than `margin` remains before it
+    /// expires
+    pub fn start(request: ResetRequest, expires: Tm, margin: Duration) -> KeepAlive {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State { expires, stopped: false }),
+            stopped: Condvar::new(),
+        });
+
+        let thread = {
+            let shared = shared.clone();
+            thread::spawn(move || run(&shared, &request, margin))
+        };
+
+        KeepAlive {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Prevents the timeout from being reset until the returned guard is dropped
+    ///
+    /// The expiration time returned by the server is set on the guard.
+    pub fn pause(&self) -> MutexGuard<'_, State> {
+        self.shared.lock()
+    }
+
+    /// Returns the expiration time, as updated by the last reset
+    ///
+    /// This waits for the request the thread may be sending.
+    pub fn expires(&self) -> Tm {
+        self.shared.lock().expires
+    }
+
+    /// Stops the thread, waiting for the request it may be sending
+    pub fn stop(&mut self) {
+        self.shared.lock().stopped = true;
+        self.shared.stopped.notify_all();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn run(shared: &Shared, request: &ResetRequest, margin: Duration) {
+    let margin = time::Duration::from_std(margin).unwrap_or_else(|_| time::Duration::zero());
+    let mut state = shared.lock();
+    let mut scheduled: Option<(Tm, Tm)> = None;
+
+    while !state.stopped {
+        // scheduled again when the expiration time changes, never sooner than halfway to it for
+        // a margin longer than the timeout
+        let reset_at = match scheduled {
+            Some((expires, reset_at)) if expires == state.expires => reset_at,
+            _ => {
+                let now = time::now_utc();
+                let remaining = state.expires - now;
+                let reset_at = now + ::std::cmp::max(remaining - margin, remaining / 2);
+                scheduled = Some((state.expires, reset_at));
+                reset_at
+            }
+        };
+
+        if let Ok(wait) = (reset_at - time::now_utc()).to_std() {
+            if wait > Duration::from_millis(0) {
+                state = match shared.stopped.wait_timeout(state, wait) {
+                    Ok((state, _)) => state,
+                    Err(poisoned) => poisoned.into_inner().0,
+                };
+                continue;
+            }
+        }
+
+        debug!("Resetting the timeout of transaction {}", request.transaction);
+        match request.send() {
+            Ok(expires) => {
+                state.expires = expires;
+                scheduled = None;
+            }
+            Err(e) => {
+                warn!("Unable to keep transaction {} alive: {}", request.transaction, e);
+                return;
+            }
+        }
+    }
+}
@@ -3,26 +3,47 @@
 pub mod transaction;
 pub mod statement;
 pub mod result;
+pub mod types;
+pub mod plan;
+pub mod bolt;
+pub mod packstream;
+pub mod builder;
+pub mod stream;
+pub mod params;
+
+mod de;
+mod keep_alive;
 
 pub use self::statement::Statement;
 pub use self::transaction::Transaction;
 pub use self::result::CypherResult;
+pub use self::plan::Plan;
 
 use std::collections::BTreeMap;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
 
 use hyper::Url;
 use hyper::client::{Client, Response};
 use hyper::header::Headers;
+use hyper::method::Method;
 use serde::de::DeserializeOwned;
 use serde_json::{self, Value};
 use serde_json::de as json_de;
 use serde_json::ser as json_ser;
 use serde_json::value as json_value;
 
+use self::bolt::BoltConnection;
+use self::params::LegacyParams;
 use self::result::{QueryResult, ResultTrait};
-use ::error::GraphError;
-
-fn send_query(client: &Client, endpoint: &str, headers: &Headers, statements: Vec<Statement>)
+use self::stream::RowStream;
+use ::auth::{self, AuthProvider};
+use ::cassette::{self, Cassette};
+use ::error::{BatchError, GraphError, Neo4jError};
+use ::retry::RetryPolicy;
+
+fn send_query(client: &Client, endpoint: &str, headers: &Headers, auth: Option<&dyn AuthProvider>,
+              cassette: Option<&Cassette>, statements: &[Statement])
     -> Result<Response, GraphError> {
 
     let mut json = BTreeMap::new();
@@ -30,38 +51,169 @@ fn send_query(client: &Client, endpoint: &str, headers: &Headers, statements: Ve
 
     let json = serde_json::to_string(&json)?;
 
-    let req = client.post(endpoint)
-        .headers(headers.clone())
-        .body(&json);
-
     debug!("Sending query:\n{}", json_ser::to_string_pretty(&json).unwrap_or(String::new()));
 
-    req.send().map_err(From::from)
+    let post = || auth::send(auth, headers, |headers| {
+        client.post(endpoint).headers(headers).body(&json).send()
+    });
+    cassette::send(cassette, Method::Post, endpoint, statements, post)
 }
 
-fn parse_response<T: DeserializeOwned + ResultTrait>(res: &mut Response) -> Result<T, GraphError> {
+/// Placeholder of the database name in the transaction endpoint of neo4j 4.0 and later, as in
+/// `http://localhost:7474/db/{databaseName}/tx`
+pub const DATABASE_PLACEHOLDER: &str = "{databaseName}";
+
+/// Database used when none is selected on a server that has several
+pub const DEFAULT_DATABASE: &str = "neo4j";
+
+/// Returns the transaction endpoint of the given database
+///
+/// Fails if a database is selected but the endpoint has no `DATABASE_PLACEHOLDER`, as servers
+/// before neo4j 4.0 have a single database.
+pub(crate) fn database_endpoint(endpoint: &str, database: Option<&str>)
+    -> Result<String, GraphError>
+{
+    if endpoint.contains(DATABASE_PLACEHOLDER) {
+        let database = database.unwrap_or(DEFAULT_DATABASE);
+        return Ok(endpoint.replace(DATABASE_PLACEHOLDER, database));
+    }
+
+    match database {
+        None => Ok(endpoint.to_owned()),
+        Some(database) => Err(GraphError::Other(format!(
+            "Unable to select the database {}, the endpoint {} has a single database",
+            database, endpoint))),
+    }
+}
+
+/// Parses the response to a request without statements, as a commit or a keep-alive
+pub(crate) fn parse_response<T, R>(res: &mut R) -> Result<T, GraphError>
+    where T: DeserializeOwned + ResultTrait, R: Read
+{
     let result: Value = json_de::from_reader(res)?;
 
-    if let Some(errors) = result.get("errors") {
-        if errors.as_array().map(|a| a.len()).unwrap_or(0) > 0 {
-            return Err(GraphError::Neo4j(json_value::from_value(errors.clone())?))
+    if let Some(errors) = errors(&result)? {
+        return Err(GraphError::Neo4j(errors));
+    }
+
+    parse_result(result)
+}
+
+/// Parses the response to the given number of statements
+///
+/// If a statement of a batch of several failed, the error is a `GraphError::Batch` with the
+/// results of the statements before it. Other errors, as for a single statement or a request
+/// rejected as a whole, are a `GraphError::Neo4j`.
+pub(crate) fn parse_batch_response<T, R>(res: &mut R, statements: usize) -> Result<T, GraphError>
+    where T: DeserializeOwned + ResultTrait, R: Read
+{
+    parse_transaction_response(res, statements).map_err(|(e, _)| e)
+}
+
+/// Parses the response to the given number of statements, as `parse_batch_response`
+///
+/// On failure, also returns whether the server rolled the transaction back, which it does on any
+/// error but rejected credentials. The response then has no `transaction` field.
+pub(crate) fn parse_transaction_response<T, R>(res: &mut R, statements: usize)
+    -> Result<T, (GraphError, bool)>
+    where T: DeserializeOwned + ResultTrait, R: Read
+{
+    let result: Value = json_de::from_reader(res).map_err(|e| (e.into(), false))?;
+
+    if let Some(errors) = errors(&result).map_err(|e| (e, false))? {
+        let unauthorized = errors.iter().any(|e| e.code.starts_with("Neo.ClientError.Security."));
+        let rolled_back = !unauthorized && result.get("transaction").is_none();
+        if statements < 2 || rejects_request(&errors) {
+            return Err((GraphError::Neo4j(errors), rolled_back));
+        }
+
+        let results = match result.get("results") {
+            Some(results) => {
+                json_value::from_value(results.clone()).map_err(|e| (e.into(), false))?
+            }
+            None => Vec::new(),
+        };
+        return Err((BatchError::new(errors, results, rolled_back).into(), rolled_back));
+    }
+
+    parse_result(result).map_err(|e| (e, false))
+}
+
+/// Whether the errors are about the request as a whole rather than one of its statements, as for
+/// rejected credentials or an expired transaction
+fn rejects_request(errors: &[Neo4jError]) -> bool {
+    errors.iter().any(|error| {
+        let mut classification = error.code.split('.').skip(1);
+        matches!((classification.next(), classification.next()),
+                 (_, Some("Security"))
+                 | (_, Some("Request"))
+                 | (Some("ClientError"), Some("Transaction")))
+    })
+}
+
+fn errors(result: &Value) -> Result<Option<Vec<Neo4jError>>, GraphError> {
+    match result.get("errors") {
+        Some(errors) if errors.as_array().map(|a| a.len()).unwrap_or(0) > 0 => {
+            Ok(Some(json_value::from_value(errors.clone())?))
         }
+        _ => Ok(None),
     }
+}
 
+fn parse_result<T: DeserializeOwned>(result: Value) -> Result<T, GraphError> {
     json_value::from_value::<T>(result).map_err(|e| {
         error!("Unable to parse response: {}", &e);
         From::from(e)
     })
 }
 
+/// Runs the statements one after the other over Bolt, until one fails
+///
+/// A failure resets the Bolt connection, which rolls its transaction back.
+pub(crate) fn run_batch<F>(statements: &[Statement], mut run: F)
+    -> Result<Vec<CypherResult>, GraphError>
+    where F: FnMut(&Statement) -> Result<CypherResult, GraphError>
+{
+    let mut results = Vec::with_capacity(statements.len());
+
+    for statement in statements {
+        match run(statement) {
+            Ok(result) => results.push(result),
+            Err(GraphError::Neo4j(errors)) if statements.len() > 1 => {
+                return Err(BatchError::new(errors, results, true).into());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(results)
+}
+
 /// Represents the cypher endpoint of a neo4j server
 ///
 /// The `Cypher` struct holds information about the cypher enpoint. It is used to create the queries
 /// that are sent to the server.
+///
+/// When created with `Cypher::bolt`, queries are sent through a Bolt connection instead of the REST
+/// endpoint.
+///
+/// On neo4j 4.0 and later, queries and transactions go to the database selected with
+/// `Cypher::set_database`, or to `DEFAULT_DATABASE`.
+///
+/// Cloning a `Cypher` is cheap, the clones share the same HTTP client and Bolt connection.
+#[derive(Clone)]
 pub struct Cypher {
-    endpoint: Url,
-    client: Client,
-    headers: Headers,
+    endpoint: String,
+    database: Option<String>,
+    client: Arc<Client>,
+    // `Headers` is not `Sync`, so it is behind a lock for `Cypher` to be shared between threads
+    headers: Arc<Mutex<Headers>>,
+    auth: Option<Arc<dyn AuthProvider>>,
+    cassette: Option<Arc<Cassette>>,
+    bolt: Option<Arc<Mutex<BoltConnection>>>,
+    retry_policy: RetryPolicy,
+    check_params: bool,
+    legacy_params: LegacyParams,
 }
 
 impl Cypher {
@@ -69,30 +221,136 @@ impl Cypher {
     ///
     /// Its arguments are the cypher transaction endpoint and the HTTP headers containing HTTP
     /// Basic Authentication, if needed.
+    ///
+    /// The `Client` is shared by the queries and the transactions created from this `Cypher`, so
+    /// they reuse its connections.
     pub fn new(endpoint: Url, client: Client, headers: Headers) -> Self {
+        Cypher::with_endpoint(endpoint.into_string(), client, headers)
+    }
+
+    /// Creates a new Cypher whose endpoint may have a `DATABASE_PLACEHOLDER`, which a `Url` would
+    /// percent-encode
+    pub(crate) fn with_endpoint(endpoint: String, client: Client, headers: Headers) -> Self {
+        Cypher {
+            endpoint,
+            database: None,
+            client: Arc::new(client),
+            headers: Arc::new(Mutex::new(headers)),
+            auth: None,
+            cassette: None,
+            bolt: None,
+            retry_policy: RetryPolicy::never(),
+            check_params: true,
+            legacy_params: LegacyParams::Keep,
+        }
+    }
+
+    /// Creates a new Cypher that sends queries through the given Bolt connection
+    ///
+    /// The endpoint is the `bolt://` URL, including credentials, used to open a new connection for
+    /// each transaction.
+    pub fn bolt(endpoint: Url, connection: BoltConnection) -> Self {
         Cypher {
-            endpoint: endpoint,
-            client: client,
-            headers: headers,
+            endpoint: endpoint.into_string(),
+            database: None,
+            client: Arc::new(Client::new()),
+            headers: Arc::new(Mutex::new(Headers::new())),
+            auth: None,
+            cassette: None,
+            bolt: Some(Arc::new(Mutex::new(connection))),
+            retry_policy: RetryPolicy::never(),
+            check_params: true,
+            legacy_params: LegacyParams::Keep,
         }
     }
 
-    fn endpoint_commit(&self) -> String {
-        format!("{}/{}", &self.endpoint, "commit")
+    fn endpoint_commit(&self, database: Option<&str>) -> Result<String, GraphError> {
+        let database = database.or(self.database.as_deref());
+        database_endpoint(&self.endpoint, database).map(|endpoint| endpoint + "/commit")
     }
 
     fn client(&self) -> &Client {
         &self.client
     }
 
-    fn headers(&self) -> &Headers {
-        &self.headers
+    fn headers(&self) -> Headers {
+        match self.headers.lock() {
+            Ok(headers) => headers.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn auth(&self) -> Option<&dyn AuthProvider> {
+        self.auth.as_deref()
+    }
+
+    /// Sets the provider of the credentials of the queries and transactions
+    ///
+    /// The provider adds its headers after the ones given to `Cypher::new`, replacing them.
+    pub fn set_auth(&mut self, auth: Option<Arc<dyn AuthProvider>>) {
+        self.auth = auth;
+    }
+
+    fn cassette(&self) -> Option<&Cassette> {
+        self.cassette.as_deref()
+    }
+
+    /// Sets the cassette recording or replaying the requests of the queries and transactions
+    pub fn set_cassette(&mut self, cassette: Option<Arc<Cassette>>) {
+        self.cassette = cassette;
+    }
+
+    /// Returns the selected database, `None` meaning the default one
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    /// Selects the database of the queries and transactions, on neo4j 4.0 and later
+    ///
+    /// Queries fail if a database is selected on a server with a single database.
+    pub fn set_database<T: Into<String>>(&mut self, database: Option<T>) {
+        self.database = database.map(Into::into);
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Sets the policy used to retry queries that fail with transient errors
+    ///
+    /// Queries are not retried by default.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub fn checks_params(&self) -> bool {
+        self.check_params
+    }
+
+    /// Sets whether the placeholders of the statements are checked against their parameters
+    /// before queries and transactions are sent
+    ///
+    /// The check is enabled by default.
+    pub fn set_check_params(&mut self, check_params: bool) {
+        self.check_params = check_params;
+    }
+
+    pub fn legacy_params(&self) -> LegacyParams {
+        self.legacy_params
+    }
+
+    /// Sets what is done with `{param}` placeholders before queries and transactions are sent
+    ///
+    /// Statements are kept as they are by default.
+    pub fn set_legacy_params(&mut self, legacy_params: LegacyParams) {
+        self.legacy_params = legacy_params;
     }
 
     /// Creates a new `CypherQuery`
     pub fn query(&self) -> CypherQuery {
         CypherQuery {
             statements: Vec::new(),
+            database: None,
             cypher: &self,
         }
     }
@@ -109,9 +367,47 @@ impl Cypher {
             .ok_or(GraphError::Other("No results returned from server".to_owned()))
     }
 
+    /// Returns the execution plan of the given `Statement` without running it
+    ///
+    /// The statement is prefixed with `EXPLAIN` unless it already starts with `EXPLAIN` or
+    /// `PROFILE`.
+    pub fn explain<S: Into<Statement>>(&self, statement: S) -> Result<Plan, GraphError> {
+        self.plan("EXPLAIN", statement.into())
+    }
+
+    /// Runs the given `Statement` and returns its execution plan with profiling counters
+    ///
+    /// The statement is prefixed with `PROFILE` unless it already starts with `EXPLAIN` or
+    /// `PROFILE`.
+    pub fn profile<S: Into<Statement>>(&self, statement: S) -> Result<Plan, GraphError> {
+        self.plan("PROFILE", statement.into())
+    }
+
+    fn plan(&self, prefix: &str, mut statement: Statement) -> Result<Plan, GraphError> {
+        let text = statement.statement().trim_start().to_owned();
+        let keyword = text.split_whitespace().next().unwrap_or("").to_uppercase();
+        if keyword != "EXPLAIN" && keyword != "PROFILE" {
+            statement.set_statement(format!("{} {}", prefix, text));
+        }
+
+        self.exec(statement)?
+            .plan
+            .ok_or_else(|| {
+                GraphError::Statement("No execution plan returned from server".to_owned())
+            })
+    }
+
     /// Creates a new `Transaction`
     pub fn transaction(&self) -> Transaction<self::transaction::Created> {
-        Transaction::new(&self.endpoint.to_string(), &self.headers)
+        let mut transaction = Transaction::with_client(&self.endpoint,
+                                                       self.client.clone(),
+                                                       self.headers());
+        transaction.set_check_params(self.check_params);
+        transaction.set_legacy_params(self.legacy_params);
+        transaction.set_auth(self.auth.clone());
+        transaction.set_cassette(self.cassette.clone());
+        transaction.set_database(self.database.clone());
+        transaction
     }
 }
 
@@ -124,6 +420,7 @@ impl Cypher {
 /// be sent to the server
 pub struct CypherQuery<'a> {
     statements: Vec<Statement>,
+    database: Option<String>,
     cypher: &'a Cypher,
 }
 
@@ -146,29 +443,96 @@ impl<'a> CypherQuery<'a> {
         self.statements = statements;
     }
 
+    /// Selects the database of the query in builder style, instead of the one of the `Cypher`
+    pub fn with_database<T: Into<String>>(mut self, database: T) -> Self {
+        self.set_database(Some(database));
+        self
+    }
+
+    /// Selects the database of the query, instead of the one of the `Cypher`
+    pub fn set_database<T: Into<String>>(&mut self, database: Option<T>) {
+        self.database = database.map(Into::into);
+    }
+
     /// Sends the query to the server
     ///
     /// The statements contained in the query are sent to the server and the results are parsed
     /// into a `Vec<CypherResult>` in order to match the response of the neo4j api.
-    pub fn send(self) -> Result<Vec<CypherResult>, GraphError> {
-        let mut res = send_query(self.cypher.client(),
-                   &self.cypher.endpoint_commit(),
-                   self.cypher.headers(),
-                   self.statements)?;
+    ///
+    /// The query is sent again if it fails with an error the `RetryPolicy` of the `Cypher` retries.
+    ///
+    /// If one of several statements fails, the error is a `GraphError::Batch` with the results of
+    /// the statements before it.
+    ///
+    /// Unless disabled with `Cypher::set_check_params`, the query is not sent if a statement has a
+    /// placeholder without a parameter or a parameter without a placeholder.
+    pub fn send(mut self) -> Result<Vec<CypherResult>, GraphError> {
+        self.prepare_statements()?;
+        self.cypher.retry_policy.run(|| self.send_once())
+    }
 
-        let result: QueryResult = parse_response(&mut res)?;
-        if result.errors().len() > 0 {
-            return Err(GraphError::Neo4j(result.errors().clone()))
+    /// Sends the query to the server and returns an iterator over the rows of the response
+    ///
+    /// The response is parsed while the rows are consumed, so only one row is kept in memory at a
+    /// time. Over Bolt, the rows are received before the iterator is returned.
+    ///
+    /// The `RetryPolicy` of the `Cypher` is not applied to streamed queries.
+    pub fn stream(mut self) -> Result<RowStream, GraphError> {
+        self.prepare_statements()?;
+
+        if self.cypher.bolt.is_some() {
+            return self.send_once().map(RowStream::from_results);
         }
 
+        let res = send_query(self.cypher.client(),
+                             &self.cypher.endpoint_commit(self.database.as_deref())?,
+                             &self.cypher.headers(),
+                             self.cypher.auth(),
+                             self.cypher.cassette(),
+                             &self.statements)?;
+
+        Ok(RowStream::from_reader(res))
+    }
+
+    fn prepare_statements(&mut self) -> Result<(), GraphError> {
+        if self.cypher.check_params {
+            params::check(&self.statements)?;
+        }
+        params::apply(self.cypher.legacy_params, &mut self.statements);
+        Ok(())
+    }
+
+    fn send_once(&self) -> Result<Vec<CypherResult>, GraphError> {
+        if let Some(ref bolt) = self.cypher.bolt {
+            if self.database.is_some() || self.cypher.database.is_some() {
+                return Err(GraphError::Bolt(
+                    "Database selection is not supported over Bolt".to_owned()));
+            }
+
+            let mut connection = bolt.lock()
+                .map_err(|_| GraphError::Bolt("Bolt connection lock poisoned".to_owned()))?;
+            return connection.run_all(&self.statements);
+        }
+
+        let mut res = send_query(self.cypher.client(),
+                   &self.cypher.endpoint_commit(self.database.as_deref())?,
+                   &self.cypher.headers(),
+                   self.cypher.auth(),
+                   self.cypher.cassette(),
+                   &self.statements)?;
+
+        let result: QueryResult = parse_batch_response(&mut res, self.statements.len())?;
         Ok(result.results)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use serde_json::json;
     use super::*;
+    use ::GraphClient;
     use ::cypher::result::Row;
+    use ::testing::{FakeResponse, FakeServer};
 
     fn get_cypher() -> Cypher {
         use hyper::{Client, Url};
@@ -188,6 +552,29 @@ mod tests {
         Cypher::new(cypher_endpoint, Client::new(), headers)
     }
 
+    #[test]
+    fn check_params_before_sending() {
+        let server = FakeServer::start().unwrap();
+        server.respond("TEST_CYPHER", FakeResponse::rows(&["n"], vec![]));
+        let mut graph = GraphClient::connect(server.url()).unwrap();
+
+        let statement = Statement::new("MATCH (n:TEST_CYPHER {name: $name}) RETURN n")
+            .with_param("nmae", "Neo").unwrap();
+
+        match graph.exec(statement.clone()) {
+            Err(GraphError::Parameters(error)) => {
+                assert_eq!(error.missing, ["name"]);
+                assert_eq!(error.unused, ["nmae"]);
+            }
+            other => panic!("Expected parameters error, got {:?}", other.map(|_| ())),
+        }
+        assert!(server.received().is_empty());
+
+        graph.set_check_params(false);
+        graph.exec(statement).unwrap();
+        assert_eq!(server.received().len(), 1);
+    }
+
     #[test]
     fn query_without_params() {
         let result = get_cypher().exec("MATCH (n:TEST_CYPHER) RETURN n").unwrap();
@@ -262,6 +649,104 @@ mod tests {
         assert_eq!(result.columns[0], "n");
     }
 
+    #[test]
+    fn query_with_stats() {
+        let server = FakeServer::start().unwrap();
+        server.respond("CREATE", FakeResponse::empty().with_stats(json!({
+            "nodes_created": 1,
+            "properties_set": 1,
+            "labels_added": 1,
+        })));
+        server.respond("DELETE", FakeResponse::empty().with_stats(json!({"nodes_deleted": 1})));
+        let graph = GraphClient::connect(server.url()).unwrap();
+
+        let statement = Statement::new("CREATE (n:TEST_CYPHER_STATS { name: 'Rust' })")
+            .with_include_stats(true);
+
+        let stats = graph.exec(statement).unwrap().stats.unwrap();
+        assert!(stats.contains_updates);
+        assert_eq!(stats.nodes_created, 1);
+        assert_eq!(stats.properties_set, 1);
+        assert_eq!(stats.labels_added, 1);
+
+        let statement = Statement::new("MATCH (n:TEST_CYPHER_STATS) DELETE n")
+            .with_include_stats(true);
+
+        let stats = graph.exec(statement).unwrap().stats.unwrap();
+        assert_eq!(stats.nodes_deleted, 1);
+
+        // the statistics are only returned when requested
+        let result = graph.exec("MATCH (n:TEST_CYPHER_STATS) DELETE n").unwrap();
+        assert!(result.stats.is_none());
+    }
+
+    #[test]
+    fn stream_query() {
+        let server = FakeServer::start().unwrap();
+        let numbers = (1..11).map(|n| vec![json!(n)]).collect();
+        server.respond("UNWIND", FakeResponse::rows(&["n"], numbers));
+        server.respond("'done'", FakeResponse::rows(&["status"], vec![vec![json!("done")]]));
+        let graph = GraphClient::connect(server.url()).unwrap();
+
+        let rows = graph.query()
+            .with_statement("UNWIND range(1, 10) AS n RETURN n")
+            .with_statement("RETURN 'done' AS status")
+            .stream()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(rows.len(), 11);
+        assert_eq!(rows[9].get::<i32>("n").unwrap(), 10);
+        assert_eq!(rows[10].statement(), 1);
+        assert_eq!(rows[10].get::<String>("status").unwrap(), "done");
+    }
+
+    #[test]
+    fn explain() {
+        let server = FakeServer::start().unwrap();
+        server.respond("EXPLAIN MATCH", FakeResponse::empty().with_plan(json!({
+            "operatorType": "ProduceResults",
+            "identifiers": ["n"],
+            "EstimatedRows": 10.0,
+            "children": [{"operatorType": "NodeByLabelScan", "identifiers": ["n"]}],
+        })));
+        let graph = GraphClient::connect(server.url()).unwrap();
+
+        let plan = graph.explain("MATCH (n:TEST_CYPHER) RETURN n").unwrap();
+
+        assert_eq!(plan.operator_type, "ProduceResults");
+        assert!(plan.rows.is_none());
+        assert!(!plan.children.is_empty());
+        assert_eq!(server.received()[0].statement, "EXPLAIN MATCH (n:TEST_CYPHER) RETURN n");
+
+        // the statement is not prefixed again, and a result without a plan is an error
+        assert!(matches!(graph.explain("EXPLAIN RETURN 1"), Err(GraphError::Statement(_))));
+        assert_eq!(server.received()[1].statement, "EXPLAIN RETURN 1");
+    }
+
+    #[test]
+    fn profile() {
+        let server = FakeServer::start().unwrap();
+        server.respond("PROFILE", FakeResponse::empty().with_plan(json!({
+            "operatorType": "ProduceResults",
+            "identifiers": ["n"],
+            "Rows": 2,
+            "DbHits": 0,
+            "children": [
+                {"operatorType": "NodeByLabelScan", "identifiers": ["n"], "Rows": 2, "DbHits": 3},
+            ],
+        })));
+        let graph = GraphClient::connect(server.url()).unwrap();
+
+        let plan = graph.profile("MATCH (n:TEST_CYPHER) RETURN n").unwrap();
+
+        assert_eq!(plan.operator_type, "ProduceResults");
+        assert_eq!(plan.rows, Some(2));
+        assert!(plan.db_hits.is_some());
+        assert_eq!(plan.total_db_hits(), 3);
+    }
+
     #[test]
     fn multiple_queries() {
         let cypher = get_cypher();
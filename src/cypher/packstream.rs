@@ -0,0 +1,571 @@
+//! Encoding and decoding of PackStream, the serialization format used by the Bolt protocol
+//!
+//! PackStream values are converted from and into `serde_json::Value` so they can be used with
+//! the same `Statement` and `CypherResult` types that the REST endpoint uses.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io::{self, Read};
+use serde_json::{self, Number};
+
+use ::error::GraphError;
+
+const TINY_STRING: u8 = 0x80;
+const TINY_LIST: u8 = 0x90;
+const TINY_MAP: u8 = 0xA0;
+const TINY_STRUCT: u8 = 0xB0;
+
+const NULL: u8 = 0xC0;
+const FLOAT_64: u8 = 0xC1;
+const FALSE: u8 = 0xC2;
+const TRUE: u8 = 0xC3;
+const INT_8: u8 = 0xC8;
+const INT_16: u8 = 0xC9;
+const INT_32: u8 = 0xCA;
+const INT_64: u8 = 0xCB;
+const BYTES_8: u8 = 0xCC;
+const BYTES_16: u8 = 0xCD;
+const BYTES_32: u8 = 0xCE;
+const STRING_8: u8 = 0xD0;
+const STRING_16: u8 = 0xD1;
+const STRING_32: u8 = 0xD2;
+const LIST_8: u8 = 0xD4;
+const LIST_16: u8 = 0xD5;
+const LIST_32: u8 = 0xD6;
+const MAP_8: u8 = 0xD8;
+const MAP_16: u8 = 0xD9;
+const MAP_32: u8 = 0xDA;
+const STRUCT_8: u8 = 0xDC;
+const STRUCT_16: u8 = 0xDD;
+
+/// Upper bound of the capacity reserved from a size read from the stream, so a bad size fails on
+/// the missing data instead of allocating up to 4 GiB
+const MAX_PREALLOCATION: usize = 1024;
+
+/// Deepest nesting of lists, maps and structures accepted when decoding, so a malicious stream
+/// fails instead of overflowing the stack
+const MAX_DEPTH: usize = 64;
+
+/// Structure signature of a graph node
+pub const NODE: u8 = 0x4E;
+/// Structure signature of a relationship
+pub const RELATIONSHIP: u8 = 0x52;
+/// Structure signature of a relationship inside a path
+pub const UNBOUND_RELATIONSHIP: u8 = 0x72;
+/// Structure signature of a path
+pub const PATH: u8 = 0x50;
+
+/// A value that can be represented in PackStream
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+    Structure(u8, Vec<Value>),
+}
+
+impl Value {
+    /// Returns the string contained in the value, if any
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the map contained in the value, if any
+    pub fn as_map(&self) -> Option<&BTreeMap<String, Value>> {
+        match *self {
+            Value::Map(ref m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Returns the list contained in the value, if any
+    pub fn as_list(&self) -> Option<&Vec<Value>> {
+        match *self {
+            Value::List(ref l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Returns a field of the structure contained in the value, or `Null` if there is none
+    pub fn as_structure_field(&self, index: usize) -> &Value {
+        match *self {
+            Value::Structure(_, ref fields) => fields.get(index).unwrap_or(&Value::Null),
+            _ => &Value::Null,
+        }
+    }
+}
+
+impl From<&serde_json::Value> for Value {
+    fn from(value: &serde_json::Value) -> Self {
+        match *value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Boolean(b),
+            serde_json::Value::Number(ref n) => {
+                match n.as_i64() {
+                    Some(i) => Value::Integer(i),
+                    None => Value::Float(n.as_f64().unwrap_or(0.0)),
+                }
+            }
+            serde_json::Value::String(ref s) => Value::String(s.clone()),
+            serde_json::Value::Array(ref a) => Value::List(a.iter().map(Value::from).collect()),
+            serde_json::Value::Object(ref o) => {
+                Value::Map(o.iter().map(|(k, v)| (k.clone(), Value::from(v))).collect())
+            }
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    /// Converts the PackStream value into JSON the same way the REST endpoint represents it in
+    /// the `row` result format: nodes and relationships become their property maps and paths
+    /// become a list alternating nodes and relationships.
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => serde_json::Value::Null,
+            Value::Boolean(b) => serde_json::Value::Bool(b),
+            Value::Integer(i) => serde_json::Value::Number(i.into()),
+            Value::Float(f) => Number::from_f64(f)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::Bytes(b) => {
+                serde_json::Value::Array(b.into_iter().map(|b| b.into()).collect())
+            }
+            Value::String(s) => serde_json::Value::String(s),
+            Value::List(l) => serde_json::Value::Array(l.into_iter().map(From::from).collect()),
+            Value::Map(m) => {
+                serde_json::Value::Object(m.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+            Value::Structure(NODE, mut fields) if fields.len() >= 3 => {
+                fields.swap_remove(2).into()
+            }
+            Value::Structure(RELATIONSHIP, mut fields) if fields.len() >= 5 => {
+                fields.swap_remove(4).into()
+            }
+            Value::Structure(UNBOUND_RELATIONSHIP, mut fields) if fields.len() >= 3 => {
+                fields.swap_remove(2).into()
+            }
+            Value::Structure(PATH, fields) => path_to_json(fields),
+            Value::Structure(_, fields) => {
+                serde_json::Value::Array(fields.into_iter().map(From::from).collect())
+            }
+        }
+    }
+}
+
+/// Unrolls a path structure (nodes, relationships, sequence) into the alternating list of nodes
+/// and relationships the REST endpoint returns
+fn path_to_json(mut fields: Vec<Value>) -> serde_json::Value {
+    if fields.len() < 3 {
+        return serde_json::Value::Array(fields.into_iter().map(From::from).collect());
+    }
+
+    let sequence = match fields.pop() {
+        Some(Value::List(sequence)) => sequence,
+        _ => Vec::new(),
+    };
+    let relationships = match fields.pop() {
+        Some(Value::List(relationships)) => relationships,
+        _ => Vec::new(),
+    };
+    let nodes = match fields.pop() {
+        Some(Value::List(nodes)) => nodes,
+        _ => Vec::new(),
+    };
+
+    let mut path = Vec::new();
+    if let Some(node) = nodes.first() {
+        path.push(node.clone().into());
+    }
+
+    for step in sequence.chunks(2) {
+        let (rel, node) = match (step.first(), step.get(1)) {
+            (Some(&Value::Integer(rel)), Some(&Value::Integer(node))) => (rel, node),
+            _ => continue,
+        };
+
+        if let Some(relationship) = relationship_index(rel).and_then(|i| relationships.get(i)) {
+            path.push(relationship.clone().into());
+        }
+        if let Some(node) = nodes.get(node as usize) {
+            path.push(node.clone().into());
+        }
+    }
+
+    serde_json::Value::Array(path)
+}
+
+/// Returns the index in the relationships of a path of a step of its sequence, which is positive
+/// when the relationship is traversed from start to end and negative otherwise, starting at 1
+pub(crate) fn relationship_index(rel: i64) -> Option<usize> {
+    usize::try_from(rel.unsigned_abs()).ok()?.checked_sub(1)
+}
+
+/// Encodes the value into the buffer
+pub fn encode(value: &Value, buf: &mut Vec<u8>) {
+    match *value {
+        Value::Null => buf.push(NULL),
+        Value::Boolean(true) => buf.push(TRUE),
+        Value::Boolean(false) => buf.push(FALSE),
+        Value::Integer(i) => encode_integer(i, buf),
+        Value::Float(f) => {
+            buf.push(FLOAT_64);
+            buf.extend_from_slice(&f.to_bits().to_be_bytes());
+        }
+        Value::Bytes(ref b) => {
+            encode_size(b.len(), None, [BYTES_8, BYTES_16, BYTES_32], buf);
+            buf.extend_from_slice(b);
+        }
+        Value::String(ref s) => {
+            encode_size(s.len(), Some(TINY_STRING), [STRING_8, STRING_16, STRING_32], buf);
+            buf.extend_from_slice(s.as_bytes());
+        }
+        Value::List(ref l) => {
+            encode_size(l.len(), Some(TINY_LIST), [LIST_8, LIST_16, LIST_32], buf);
+            for item in l {
+                encode(item, buf);
+            }
+        }
+        Value::Map(ref m) => {
+            encode_size(m.len(), Some(TINY_MAP), [MAP_8, MAP_16, MAP_32], buf);
+            for (k, v) in m {
+                encode(&Value::String(k.clone()), buf);
+                encode(v, buf);
+            }
+        }
+        Value::Structure(signature, ref fields) => {
+            if fields.len() < 0x10 {
+                buf.push(TINY_STRUCT | fields.len() as u8);
+            } else if fields.len() <= u8::MAX as usize {
+                buf.push(STRUCT_8);
+                buf.push(fields.len() as u8);
+            } else {
+                buf.push(STRUCT_16);
+                buf.extend_from_slice(&(fields.len() as u16).to_be_bytes());
+            }
+            buf.push(signature);
+            for field in fields {
+                encode(field, buf);
+            }
+        }
+    }
+}
+
+fn encode_integer(i: i64, buf: &mut Vec<u8>) {
+    if (-0x10..0x80).contains(&i) {
+        buf.push(i as i8 as u8);
+    } else if (i8::MIN as i64..=i8::MAX as i64).contains(&i) {
+        buf.push(INT_8);
+        buf.push(i as i8 as u8);
+    } else if (i16::MIN as i64..=i16::MAX as i64).contains(&i) {
+        buf.push(INT_16);
+        buf.extend_from_slice(&(i as i16).to_be_bytes());
+    } else if (i32::MIN as i64..=i32::MAX as i64).contains(&i) {
+        buf.push(INT_32);
+        buf.extend_from_slice(&(i as i32).to_be_bytes());
+    } else {
+        buf.push(INT_64);
+        buf.extend_from_slice(&i.to_be_bytes());
+    }
+}
+
+fn encode_size(size: usize, tiny: Option<u8>, markers: [u8; 3], buf: &mut Vec<u8>) {
+    match tiny {
+        Some(marker) if size < 0x10 => buf.push(marker | size as u8),
+        _ if size <= u8::MAX as usize => {
+            buf.push(markers[0]);
+            buf.push(size as u8);
+        }
+        _ if size <= u16::MAX as usize => {
+            buf.push(markers[1]);
+            buf.extend_from_slice(&(size as u16).to_be_bytes());
+        }
+        _ => {
+            buf.push(markers[2]);
+            buf.extend_from_slice(&(size as u32).to_be_bytes());
+        }
+    }
+}
+
+/// Decodes a single value from the reader
+pub fn decode<R: Read>(reader: &mut R) -> Result<Value, GraphError> {
+    decode_value(reader, 0)
+}
+
+fn decode_value<R: Read>(reader: &mut R, depth: usize) -> Result<Value, GraphError> {
+    if depth > MAX_DEPTH {
+        return Err(GraphError::Bolt(
+            format!("PackStream value nested deeper than {} levels", MAX_DEPTH)));
+    }
+
+    let marker = read_u8(reader)?;
+
+    let value = match marker {
+        NULL => Value::Null,
+        TRUE => Value::Boolean(true),
+        FALSE => Value::Boolean(false),
+        FLOAT_64 => Value::Float(f64::from_bits(u64::from_be_bytes(read_array(reader)?))),
+        INT_8 => Value::Integer(read_u8(reader)? as i8 as i64),
+        INT_16 => Value::Integer(i16::from_be_bytes(read_array(reader)?) as i64),
+        INT_32 => Value::Integer(i32::from_be_bytes(read_array(reader)?) as i64),
+        INT_64 => Value::Integer(i64::from_be_bytes(read_array(reader)?)),
+        BYTES_8 | BYTES_16 | BYTES_32 => {
+            let size = read_size(reader, marker - BYTES_8)?;
+            Value::Bytes(read_bytes(reader, size)?)
+        }
+        STRING_8 | STRING_16 | STRING_32 => {
+            let size = read_size(reader, marker - STRING_8)?;
+            Value::String(String::from_utf8(read_bytes(reader, size)?)?)
+        }
+        LIST_8 | LIST_16 | LIST_32 => {
+            let size = read_size(reader, marker - LIST_8)?;
+            decode_list(reader, size, depth)?
+        }
+        MAP_8 | MAP_16 | MAP_32 => {
+            let size = read_size(reader, marker - MAP_8)?;
+            decode_map(reader, size, depth)?
+        }
+        STRUCT_8 => {
+            let size = read_u8(reader)? as usize;
+            decode_struct(reader, size, depth)?
+        }
+        STRUCT_16 => {
+            let size = u16::from_be_bytes(read_array(reader)?) as usize;
+            decode_struct(reader, size, depth)?
+        }
+        m if m & 0xF0 == TINY_STRING => {
+            let size = (m & 0x0F) as usize;
+            Value::String(String::from_utf8(read_bytes(reader, size)?)?)
+        }
+        m if m & 0xF0 == TINY_LIST => decode_list(reader, (m & 0x0F) as usize, depth)?,
+        m if m & 0xF0 == TINY_MAP => decode_map(reader, (m & 0x0F) as usize, depth)?,
+        m if m & 0xF0 == TINY_STRUCT => decode_struct(reader, (m & 0x0F) as usize, depth)?,
+        m if !(0x80..0xF0).contains(&m) => Value::Integer(m as i8 as i64),
+        m => return Err(GraphError::Bolt(format!("Unknown PackStream marker 0x{:02X}", m))),
+    };
+
+    Ok(value)
+}
+
+fn decode_list<R: Read>(reader: &mut R, size: usize, depth: usize) -> Result<Value, GraphError> {
+    let mut list = Vec::with_capacity(size.min(MAX_PREALLOCATION));
+    for _ in 0..size {
+        list.push(decode_value(reader, depth + 1)?);
+    }
+
+    Ok(Value::List(list))
+}
+
+fn decode_map<R: Read>(reader: &mut R, size: usize, depth: usize) -> Result<Value, GraphError> {
+    let mut map = BTreeMap::new();
+    for _ in 0..size {
+        let key = match decode_value(reader, depth + 1)? {
+            Value::String(key) => key,
+            other => {
+                return Err(GraphError::Bolt(format!("Invalid PackStream map key: {:?}", other)))
+            }
+        };
+        map.insert(key, decode_value(reader, depth + 1)?);
+    }
+
+    Ok(Value::Map(map))
+}
+
+fn decode_struct<R: Read>(reader: &mut R, size: usize, depth: usize) -> Result<Value, GraphError> {
+    let signature = read_u8(reader)?;
+    let mut fields = Vec::with_capacity(size.min(MAX_PREALLOCATION));
+    for _ in 0..size {
+        fields.push(decode_value(reader, depth + 1)?);
+    }
+
+    if signature == PATH {
+        check_path(&fields)?;
+    }
+
+    Ok(Value::Structure(signature, fields))
+}
+
+/// Checks that the sequence of a path only refers to its own nodes and relationships
+fn check_path(fields: &[Value]) -> Result<(), GraphError> {
+    let (nodes, relationships, sequence) = match fields {
+        [Value::List(nodes), Value::List(relationships), Value::List(sequence), ..] => {
+            (nodes, relationships, sequence)
+        }
+        _ => return Ok(()),
+    };
+
+    for step in sequence.chunks(2) {
+        if let [Value::Integer(rel), Value::Integer(node)] = *step {
+            let rel_valid = relationship_index(rel).is_some_and(|i| i < relationships.len());
+            let node_valid = usize::try_from(node).is_ok_and(|i| i < nodes.len());
+
+            if !rel_valid || !node_valid {
+                return Err(GraphError::Bolt(
+                    format!("Invalid PackStream path step: [{}, {}]", rel, node)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the size that follows a sized marker, where `width` is 0, 1 or 2 for 8, 16 or 32 bits
+fn read_size<R: Read>(reader: &mut R, width: u8) -> Result<usize, GraphError> {
+    let size = match width {
+        0 => read_u8(reader)? as usize,
+        1 => u16::from_be_bytes(read_array(reader)?) as usize,
+        _ => u32::from_be_bytes(read_array(reader)?) as usize,
+    };
+
+    Ok(size)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8, GraphError> {
+    let buf: [u8; 1] = read_array(reader)?;
+    Ok(buf[0])
+}
+
+fn read_array<R: Read, A: AsMut<[u8]> + Default>(reader: &mut R) -> Result<A, GraphError> {
+    let mut buf = A::default();
+    reader.read_exact(buf.as_mut())?;
+    Ok(buf)
+}
+
+fn read_bytes<R: Read>(reader: &mut R, size: usize) -> Result<Vec<u8>, GraphError> {
+    let mut buf = Vec::with_capacity(size.min(MAX_PREALLOCATION));
+    reader.take(size as u64).read_to_end(&mut buf)?;
+    if buf.len() < size {
+        let e = io::Error::new(io::ErrorKind::UnexpectedEof, "PackStream value truncated");
+        return Err(e.into());
+    }
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn roundtrip(value: Value) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode(&value, &mut buf);
+        let decoded = decode(&mut &buf[..]).unwrap();
+        assert_eq!(value, decoded);
+        buf
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(roundtrip(Value::Integer(1)), vec![0x01]);
+        assert_eq!(roundtrip(Value::Integer(-16)), vec![0xF0]);
+        assert_eq!(roundtrip(Value::Integer(-17)), vec![INT_8, 0xEF]);
+        assert_eq!(roundtrip(Value::Integer(128)), vec![INT_16, 0x00, 0x80]);
+        assert_eq!(roundtrip(Value::Integer(40000)), vec![INT_32, 0x00, 0x00, 0x9C, 0x40]);
+        assert_eq!(roundtrip(Value::Integer(i64::MAX)).len(), 9);
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(roundtrip(Value::String("a".to_owned())), vec![0x81, 0x61]);
+        assert_eq!(roundtrip(Value::String("a".repeat(16)))[..2], [STRING_8, 16]);
+        assert_eq!(roundtrip(Value::String("a".repeat(256)))[..3], [STRING_16, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn collections() {
+        let mut map = BTreeMap::new();
+        map.insert("name".to_owned(), Value::String("Rust".to_owned()));
+        map.insert("safe".to_owned(), Value::Boolean(true));
+        map.insert("level".to_owned(), Value::Float(1.5));
+        map.insert("nothing".to_owned(), Value::Null);
+
+        roundtrip(Value::List(vec![Value::Integer(1), Value::Map(map)]));
+        roundtrip(Value::List((0..300).map(Value::Integer).collect()));
+        roundtrip(Value::Bytes(vec![1, 2, 3]));
+        roundtrip(Value::Structure(0x10, vec![Value::String("RETURN 1".to_owned())]));
+    }
+
+    #[test]
+    fn truncated_sizes() {
+        for marker in &[BYTES_32, STRING_32, LIST_32, MAP_32] {
+            let buf = [*marker, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+            assert!(decode(&mut &buf[..]).is_err());
+        }
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let mut buf = vec![0x91; MAX_DEPTH];
+        buf.push(0x01);
+        assert!(decode(&mut &buf[..]).is_ok());
+
+        let buf = vec![0x91; 100_000];
+        match decode(&mut &buf[..]) {
+            Err(GraphError::Bolt(message)) => assert!(message.contains("nested")),
+            other => panic!("Expected Bolt error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_path_steps() {
+        let node = Value::Structure(NODE, vec![
+            Value::Integer(1),
+            Value::List(vec![]),
+            Value::Map(BTreeMap::new()),
+        ]);
+        let relationship = Value::Structure(UNBOUND_RELATIONSHIP, vec![
+            Value::Integer(1),
+            Value::String("KNOWS".to_owned()),
+            Value::Map(BTreeMap::new()),
+        ]);
+
+        for &(rel, node_index) in &[(i64::MIN, 0), (0, 0), (2, 0), (-2, 0), (1, 1), (1, -1)] {
+            let path = Value::Structure(PATH, vec![
+                Value::List(vec![node.clone()]),
+                Value::List(vec![relationship.clone()]),
+                Value::List(vec![Value::Integer(rel), Value::Integer(node_index)]),
+            ]);
+
+            let mut buf = Vec::new();
+            encode(&path, &mut buf);
+            match decode(&mut &buf[..]) {
+                Err(GraphError::Bolt(message)) => assert!(message.contains("path step")),
+                other => panic!("Expected Bolt error for [{}, {}], got {:?}",
+                                rel, node_index, other),
+            }
+
+            assert!(serde_json::Value::from(path).is_array());
+        }
+
+        assert_eq!(relationship_index(-1), Some(0));
+    }
+
+    #[test]
+    fn json_conversion() {
+        let json = json!({"name": "Rust", "values": [1, 2.5, true, null]});
+        let value = Value::from(&json);
+        assert_eq!(serde_json::Value::from(value), json);
+    }
+
+    #[test]
+    fn node_into_json() {
+        let mut properties = BTreeMap::new();
+        properties.insert("name".to_owned(), Value::String("Rust".to_owned()));
+
+        let node = Value::Structure(NODE, vec![
+            Value::Integer(1),
+            Value::List(vec![Value::String("LANG".to_owned())]),
+            Value::Map(properties),
+        ]);
+
+        assert_eq!(serde_json::Value::from(node), json!({"name": "Rust"}));
+    }
+}
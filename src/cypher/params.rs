@@ -0,0 +1,381 @@
+//! Analysis of the parameter placeholders of a statement
+//!
+//! Both the legacy `{param}` and the current `$param` placeholders are recognized. Placeholders
+//! inside string literals, quoted identifiers and comments are ignored, and braces following a
+//! variable or a closing parenthesis are read as a map projection such as `n {name}`.
+//!
+//! Before a query or a transaction is sent, the placeholders of each statement are compared with
+//! its parameters, so that a typo fails with `GraphError::Parameters` instead of a round trip to
+//! the server. The check can be disabled with `GraphClient::set_check_params`.
+//!
+//! Neo4j 4.0 removed the `{param}` syntax. With `GraphClient::set_legacy_params`, statements using
+//! it are either reported with a warning or rewritten to `$param` before they are sent.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rusted_cypher::cypher::params::{self, PlaceholderStyle};
+//! let statement = "MATCH (n {name: $name}) // $ignored\nRETURN n LIMIT {limit}";
+//! let placeholders = params::placeholders(statement);
+//!
+//! assert_eq!(placeholders[0].name, "name");
+//! assert_eq!(placeholders[0].style, PlaceholderStyle::Dollar);
+//! assert_eq!(placeholders[0].span, 16..21);
+//! assert_eq!(placeholders[1].name, "limit");
+//! assert_eq!(placeholders[1].style, PlaceholderStyle::Braces);
+//! ```
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use ::error::GraphError;
+use super::Statement;
+
+/// Syntax of a parameter placeholder
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PlaceholderStyle {
+    /// `{param}`, deprecated in neo4j 3.0 and removed in 4.0
+    Braces,
+    /// `$param`
+    Dollar,
+}
+
+/// A parameter placeholder found in a statement
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Placeholder {
+    /// Name of the parameter, without quotes
+    pub name: String,
+    pub style: PlaceholderStyle,
+    /// Byte range of the whole placeholder in the statement, including `$` or the braces
+    pub span: Range<usize>,
+}
+
+/// What is done with `{param}` placeholders before statements are sent
+///
+/// Servers before neo4j 3.0 do not accept `$param`, so statements are always kept as they are on
+/// them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LegacyParams {
+    /// Statements are sent as they are
+    #[default]
+    Keep,
+    /// A warning is logged for each statement using `{param}`
+    Warn,
+    /// `{param}` placeholders are rewritten to `$param`
+    Rewrite,
+}
+
+/// Returns the parameter placeholders of the statement text, in order of appearance
+pub fn placeholders(statement: &str) -> Vec<Placeholder> {
+    let bytes = statement.as_bytes();
+    let mut placeholders = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        pos = match (bytes[pos], bytes.get(pos + 1)) {
+            (b'\'', _) | (b'"', _) => skip_string(bytes, pos),
+            (b'`', _) => skip_quoted(bytes, pos),
+            (b'/', Some(&b'/')) => find(bytes, pos + 2, b"\n").map_or(bytes.len(), |end| end + 1),
+            (b'/', Some(&b'*')) => find(bytes, pos + 2, b"*/").map_or(bytes.len(), |end| end + 2),
+            (b'$', _) => match parse_name(statement, pos + 1) {
+                Some((name, end)) => {
+                    placeholders.push(Placeholder {
+                        name,
+                        style: PlaceholderStyle::Dollar,
+                        span: pos..end,
+                    });
+                    end
+                }
+                None => pos + 1,
+            },
+            (b'{', _) if !is_projection(statement, pos) => match parse_braces(statement, pos) {
+                Some((name, end)) => {
+                    placeholders.push(Placeholder {
+                        name,
+                        style: PlaceholderStyle::Braces,
+                        span: pos..end,
+                    });
+                    end
+                }
+                None => pos + 1,
+            },
+            _ => pos + 1,
+        };
+    }
+
+    placeholders
+}
+
+/// Checks the placeholders of each statement against its parameters
+pub(crate) fn check(statements: &[Statement]) -> Result<(), GraphError> {
+    statements.iter().try_for_each(Statement::check_params)
+}
+
+/// Rewrites the `{param}` placeholders of the statement text to `$param`
+///
+/// Map literals, map projections, strings, quoted identifiers and comments are left untouched.
+///
+/// # Examples
+///
+/// ```
+/// # use rusted_cypher::cypher::params;
+/// let statement = "MATCH (n {name: {name}}) WHERE n.bio = '{bio}' RETURN n {.name}";
+///
+/// assert_eq!(params::rewrite(statement),
+///            "MATCH (n {name: $name}) WHERE n.bio = '{bio}' RETURN n {.name}");
+/// ```
+pub fn rewrite(statement: &str) -> Cow<'_, str> {
+    let legacy = placeholders(statement).into_iter()
+        .filter(|placeholder| placeholder.style == PlaceholderStyle::Braces)
+        .collect::<Vec<_>>();
+
+    if legacy.is_empty() {
+        return Cow::Borrowed(statement);
+    }
+
+    let mut rewritten = String::with_capacity(statement.len());
+    let mut pos = 0;
+
+    for placeholder in legacy {
+        rewritten.push_str(&statement[pos..placeholder.span.start]);
+        rewritten.push('$');
+
+        if is_plain_name(&placeholder.name) {
+            rewritten.push_str(&placeholder.name);
+        } else {
+            rewritten.push('`');
+            rewritten.push_str(&placeholder.name.replace('`', "``"));
+            rewritten.push('`');
+        }
+
+        pos = placeholder.span.end;
+    }
+
+    rewritten.push_str(&statement[pos..]);
+    Cow::Owned(rewritten)
+}
+
+/// Applies the `LegacyParams` mode to the statements
+pub(crate) fn apply(legacy_params: LegacyParams, statements: &mut [Statement]) {
+    match legacy_params {
+        LegacyParams::Keep => {}
+        LegacyParams::Warn => {
+            for statement in statements.iter() {
+                let legacy = placeholders(statement.statement()).iter()
+                    .any(|placeholder| placeholder.style == PlaceholderStyle::Braces);
+
+                if legacy {
+                    warn!("Statement uses the {{param}} syntax removed in neo4j 4.0: {}",
+                          statement.statement());
+                }
+            }
+        }
+        LegacyParams::Rewrite => {
+            for statement in statements.iter_mut() {
+                if let Cow::Owned(rewritten) = rewrite(statement.statement()) {
+                    statement.set_statement(rewritten);
+                }
+            }
+        }
+    }
+}
+
+/// Whether the name can follow `$` without being quoted
+fn is_plain_name(name: &str) -> bool {
+    match name.chars().next() {
+        Some(c) if c.is_ascii_digit() => name.chars().all(|c| c.is_ascii_digit()),
+        Some(c) if c.is_alphabetic() || c == '_' => {
+            name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Returns the position after the string literal starting at `start`
+fn skip_string(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut pos = start + 1;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            c if c == quote => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+/// Returns the position after the quoted identifier starting at `start`
+///
+/// A backtick inside the identifier is escaped by doubling it, which reads as two identifiers in a
+/// row and needs no special handling.
+fn skip_quoted(bytes: &[u8], start: usize) -> usize {
+    find(bytes, start + 1, b"`").map_or(bytes.len(), |end| end + 1)
+}
+
+fn find(bytes: &[u8], start: usize, needle: &[u8]) -> Option<usize> {
+    bytes[start..].windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| start + index)
+}
+
+/// Parses the name of a parameter at `start`, returning it with the position after it
+///
+/// Names are identifiers, quoted identifiers or, for positional parameters, numbers.
+fn parse_name(statement: &str, start: usize) -> Option<(String, usize)> {
+    let rest = &statement[start..];
+
+    if rest.starts_with('`') {
+        let mut name = String::new();
+        let mut chars = rest.char_indices().skip(1).peekable();
+        while let Some((index, c)) = chars.next() {
+            if c != '`' {
+                name.push(c);
+            } else if chars.peek().map(|&(_, c)| c) == Some('`') {
+                name.push(c);
+                chars.next();
+            } else {
+                return Some((name, start + index + 1));
+            }
+        }
+        return None;
+    }
+
+    let first = rest.chars().next()?;
+    let is_name_char: fn(char) -> bool = if first.is_ascii_digit() {
+        |c| c.is_ascii_digit()
+    } else if first.is_alphabetic() || first == '_' {
+        |c| c.is_alphanumeric() || c == '_'
+    } else {
+        return None;
+    };
+
+    let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+    Some((rest[..len].to_owned(), start + len))
+}
+
+/// Keywords after which braces start an expression rather than a map projection
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "AND", "BY", "CASE", "CONTAINS", "DISTINCT", "ELSE", "IN", "IS", "LIMIT", "NOT", "OR",
+    "RETURN", "SKIP", "THEN", "UNWIND", "WHEN", "WHERE", "WITH", "XOR",
+];
+
+/// Whether the braces at `start` are a map projection, like `n {name}` or `f(n) {.name}`
+///
+/// Braces following a variable are read as a projection, unless the variable is a keyword or is
+/// part of a pattern, like the properties of `(n {props})` or `-[r:KNOWS {props}]->`.
+fn is_projection(statement: &str, start: usize) -> bool {
+    let before = statement[..start].trim_end();
+    if before.ends_with(')') {
+        return true;
+    }
+
+    let word_start = if let Some(quoted) = before.strip_suffix('`') {
+        match quoted.rfind('`') {
+            Some(index) => index,
+            None => return false,
+        }
+    } else {
+        let word_start = before.char_indices().rev()
+            .find(|&(_, c)| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |(index, c)| index + c.len_utf8());
+
+        let word = &before[word_start..];
+        let is_keyword = EXPRESSION_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word));
+        if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit()) || is_keyword {
+            return false;
+        }
+        word_start
+    };
+
+    !before[..word_start].trim_end().ends_with(&[':', '(', '['][..])
+}
+
+/// Parses a `{param}` placeholder at `start`, which is not to be confused with a map literal
+fn parse_braces(statement: &str, start: usize) -> Option<(String, usize)> {
+    let skip_whitespace = |pos: usize| {
+        statement[pos..].find(|c: char| !c.is_whitespace()).map_or(statement.len(), |n| pos + n)
+    };
+
+    let (name, end) = parse_name(statement, skip_whitespace(start + 1))?;
+    let end = skip_whitespace(end);
+
+    if statement[end..].starts_with('}') {
+        Some((name, end + 1))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(statement: &str) -> Vec<String> {
+        placeholders(statement).into_iter().map(|p| p.name).collect()
+    }
+
+    #[test]
+    fn find_placeholders() {
+        let statement = "MATCH (n:PERSON {name: $name}) WHERE n.age > { age } RETURN n LIMIT $0";
+        let found = placeholders(statement);
+
+        assert_eq!(found.len(), 3);
+        assert_eq!(&statement[found[0].span.clone()], "$name");
+        assert_eq!(found[1].name, "age");
+        assert_eq!(found[1].style, PlaceholderStyle::Braces);
+        assert_eq!(&statement[found[1].span.clone()], "{ age }");
+        assert_eq!(found[2].name, "0");
+
+        assert_eq!(names("RETURN $`first name`, {`a``b`}, $über"), ["first name", "a`b", "über"]);
+    }
+
+    #[test]
+    fn skip_literals_and_comments() {
+        let statement = r#"
+            // MATCH (n {name: $commented})
+            MATCH (n) /* {block} $comment */
+            WHERE n.name = 'it\'s $single' OR n.name = "{double}" OR n.`$quoted` = $value
+            RETURN n {.name}, {key: $map}, n {name}, f(n) { age }, $ alone, '$unterminated
+        "#;
+
+        assert_eq!(names(statement), ["value", "map"]);
+        assert_eq!(names("CREATE (n {props})-[r:KNOWS { rel }]->(:PERSON {other}) RETURN {n}"),
+                   ["props", "rel", "other", "n"]);
+        assert_eq!(names("UNWIND {list} AS x WITH x WHERE x IN {set} RETURN x {.name, age}"),
+                   ["list", "set"]);
+    }
+
+    #[test]
+    fn rewrite_legacy_placeholders() {
+        let statement = "MATCH (n {name: { name }, tags: ['{tag}']}) // {comment}\n\
+                         SET n += {props}, n.`{quoted}` = {`first name`} RETURN {0}, $done";
+
+        assert_eq!(rewrite(statement),
+                   "MATCH (n {name: $name, tags: ['{tag}']}) // {comment}\n\
+                    SET n += $props, n.`{quoted}` = $`first name` RETURN $0, $done");
+
+        let statement = "RETURN {key: $value}, n {name}, n {.name, age: {age}}";
+        assert_eq!(rewrite(statement), "RETURN {key: $value}, n {name}, n {.name, age: $age}");
+
+        let statement = "RETURN {key: $value}, n {name}";
+        assert!(match rewrite(statement) {
+            Cow::Borrowed(rewritten) => rewritten == statement,
+            Cow::Owned(_) => false,
+        });
+    }
+
+    #[test]
+    fn apply_legacy_params() {
+        let mut statements = vec![Statement::new("RETURN {a}"), Statement::new("RETURN $b")];
+
+        apply(LegacyParams::Warn, &mut statements);
+        assert_eq!(statements[0].statement(), "RETURN {a}");
+
+        apply(LegacyParams::Rewrite, &mut statements);
+        assert_eq!(statements[0].statement(), "RETURN $a");
+        assert_eq!(statements[1].statement(), "RETURN $b");
+    }
+}
@@ -4,17 +4,130 @@ use std::error::Error;
 use std::fmt;
 use std::string::FromUtf8Error;
 use std::io;
+use std::str::FromStr;
 use hyper;
 use serde_json;
 use time;
 use semver::SemVerError;
 
+use cypher::CypherResult;
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Neo4jError {
     pub message: String,
     pub code: String,
 }
 
+impl Neo4jError {
+    /// Parses the `code` of the error
+    ///
+    /// Returns `None` if the code is not in the `Neo.Classification.Category.Title` format
+    pub fn status(&self) -> Option<StatusCode> {
+        self.code.parse().ok()
+    }
+
+    pub fn classification(&self) -> Option<Classification> {
+        self.status().map(|status| status.classification)
+    }
+
+    /// Returns `true` if the operation may succeed when retried, as for deadlocks
+    pub fn is_transient(&self) -> bool {
+        self.classification() == Some(Classification::TransientError)
+    }
+
+    /// Returns `true` if the statement violated a uniqueness or existence constraint
+    pub fn is_constraint_violation(&self) -> bool {
+        self.status().is_some_and(|status| {
+            status.classification == Classification::ClientError
+                && (status.category == "Schema" || status.category == "Statement")
+                && ["ConstraintValidationFailed", "ConstraintViolation",
+                    "ConstraintVerificationFailed"].contains(&status.title.as_str())
+        })
+    }
+
+    /// Returns `true` if the server rejected the credentials
+    pub fn is_auth_failure(&self) -> bool {
+        self.status().is_some_and(|status| {
+            status.category == "Security"
+                && ["Unauthorized", "AuthenticationRateLimit", "CredentialsExpired",
+                    "AuthorizationFailed", "TokenExpired"].contains(&status.title.as_str())
+        })
+    }
+}
+
+/// Classification of a neo4j status code
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Classification {
+    /// The request is invalid and should not be retried without changes
+    ClientError,
+    /// The request succeeded, but the server has a warning about it
+    ClientNotification,
+    /// The request failed but may succeed if retried
+    TransientError,
+    /// The database failed to process the request
+    DatabaseError,
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let classification = match *self {
+            Classification::ClientError => "ClientError",
+            Classification::ClientNotification => "ClientNotification",
+            Classification::TransientError => "TransientError",
+            Classification::DatabaseError => "DatabaseError",
+        };
+        f.write_str(classification)
+    }
+}
+
+/// A neo4j status code, as in `Neo.ClientError.Statement.SyntaxError`
+///
+/// ```
+/// # use rusted_cypher::error::{Classification, StatusCode};
+/// let status: StatusCode = "Neo.ClientError.Statement.SyntaxError".parse().unwrap();
+///
+/// assert_eq!(status.classification, Classification::ClientError);
+/// assert_eq!(status.category, "Statement");
+/// assert_eq!(status.title, "SyntaxError");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StatusCode {
+    pub classification: Classification,
+    pub category: String,
+    pub title: String,
+}
+
+impl FromStr for StatusCode {
+    type Err = GraphError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = code.split('.').collect();
+
+        let classification = match parts.as_slice() {
+            ["Neo", classification, _, _] => match *classification {
+                "ClientError" => Classification::ClientError,
+                "ClientNotification" => Classification::ClientNotification,
+                "TransientError" => Classification::TransientError,
+                "DatabaseError" => Classification::DatabaseError,
+                _ => return Err(GraphError::Other(format!("Unknown status code: {}", code))),
+            },
+            _ => return Err(GraphError::Other(format!("Invalid status code: {}", code))),
+        };
+
+        Ok(StatusCode {
+            classification,
+            category: parts[2].to_owned(),
+            title: parts[3].to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Neo.{}.{}.{}", self.classification, self.category, self.title)
+    }
+}
+
 #[derive(Debug)]
 pub struct TimeParseError(time::ParseError, String);
 
@@ -30,14 +143,97 @@ impl Error for TimeParseError {
     }
 }
 
+/// Mismatch between the placeholders of a statement and its parameters
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterError {
+    pub statement: String,
+    /// Placeholders without a parameter
+    pub missing: Vec<String>,
+    /// Parameters without a placeholder
+    pub unused: Vec<String>,
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid parameters for statement `{}`", self.statement)?;
+        if !self.missing.is_empty() {
+            write!(f, ", missing: {}", self.missing.join(", "))?;
+        }
+        if !self.unused.is_empty() {
+            write!(f, ", unused: {}", self.unused.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParameterError {
+    fn description(&self) -> &str {
+        "invalid statement parameters"
+    }
+}
+
+/// Failure of a statement in a batch of several statements sent together
+#[derive(Clone, Debug)]
+pub struct BatchError {
+    pub errors: Vec<Neo4jError>,
+    /// Results of the statements that succeeded before the failing one
+    pub results: Vec<CypherResult>,
+    /// Index of the failing statement in the batch, the first statement without a result
+    ///
+    /// A request rejected as a whole, as for an expired transaction or rejected credentials, is a
+    /// `GraphError::Neo4j` instead.
+    pub index: usize,
+    /// Whether the server rolled the transaction back, which it does on any failure on neo4j
+    pub rolled_back: bool,
+}
+
+impl BatchError {
+    pub fn new(errors: Vec<Neo4jError>, results: Vec<CypherResult>, rolled_back: bool) -> Self {
+        BatchError {
+            errors,
+            index: results.len(),
+            results,
+            rolled_back,
+        }
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Statement {} of the batch failed", self.index)?;
+        for error in &self.errors {
+            write!(f, ", {}: {}", error.code, error.message)?;
+        }
+        if self.rolled_back {
+            write!(f, ", the transaction was rolled back")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for BatchError {
+    fn description(&self) -> &str {
+        "statement failed in a batch"
+    }
+}
+
 quick_error! {
     #[derive(Debug)]
     pub enum GraphError {
         Neo4j(err: Vec<Neo4jError>) {
             from()
         }
+        Batch(err: BatchError) {
+            from()
+            display("{}", err)
+        }
         Statement(err: String)
+        Parameters(err: ParameterError) {
+            from()
+            display("{}", err)
+        }
         Transaction(err: String)
+        Bolt(err: String)
         Io(err: io::Error) {
             from()
         }
@@ -64,3 +260,54 @@ quick_error! {
         }
     }
 }
+
+#[cfg(feature = "async")]
+impl From<::hyper14::Error> for GraphError {
+    fn from(error: ::hyper14::Error) -> Self {
+        GraphError::Io(io::Error::other(error))
+    }
+}
+
+#[cfg(feature = "tls")]
+impl From<::native_tls::Error> for GraphError {
+    fn from(error: ::native_tls::Error) -> Self {
+        GraphError::Io(io::Error::other(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(code: &str) -> Neo4jError {
+        Neo4jError { message: String::new(), code: code.to_owned() }
+    }
+
+    #[test]
+    fn parse_status_code() {
+        let status = error("Neo.TransientError.Transaction.DeadlockDetected").status().unwrap();
+
+        assert_eq!(status.classification, Classification::TransientError);
+        assert_eq!(status.category, "Transaction");
+        assert_eq!(status.title, "DeadlockDetected");
+        assert_eq!(status.to_string(), "Neo.TransientError.Transaction.DeadlockDetected");
+
+        assert!(error("Neo.ClientError.Statement").status().is_none());
+        assert!(error("Neo.UnknownError.Statement.SyntaxError").status().is_none());
+    }
+
+    #[test]
+    fn error_helpers() {
+        assert!(error("Neo.TransientError.Transaction.DeadlockDetected").is_transient());
+        assert!(!error("Neo.ClientError.Statement.SyntaxError").is_transient());
+
+        let error_code = "Neo.ClientError.Schema.ConstraintValidationFailed";
+        assert!(error(error_code).is_constraint_violation());
+        assert!(error("Neo.ClientError.Schema.ConstraintViolation").is_constraint_violation());
+        assert!(!error("Neo.ClientError.Schema.ConstraintAlreadyExists").is_constraint_violation());
+
+        assert!(error("Neo.ClientError.Security.Unauthorized").is_auth_failure());
+        assert!(!error("Neo.ClientError.Security.Forbidden").is_auth_failure());
+        assert!(!error("invalid").is_auth_failure());
+    }
+}
@@ -138,10 +138,36 @@ extern crate quick_error;
 #[macro_use]
 extern crate log;
 
+#[cfg(feature = "derive")]
+extern crate rusted_cypher_derive;
+
+#[cfg(feature = "tls")]
+extern crate native_tls;
+
+#[cfg(feature = "async")]
+extern crate futures;
+#[cfg(feature = "async")]
+extern crate hyper14;
+
+#[cfg(all(test, feature = "async"))]
+extern crate tokio;
+
 pub mod cypher;
 pub mod graph;
 pub mod error;
+pub mod pool;
+pub mod retry;
+pub mod auth;
+pub mod net;
+pub mod cassette;
+#[cfg(feature = "async")]
+pub mod async_client;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
-pub use graph::GraphClient;
+pub use graph::{GraphClient, GraphClientBuilder};
 pub use cypher::Statement;
 pub use error::GraphError;
+pub use cypher::result::FromRow;
+#[cfg(feature = "derive")]
+pub use rusted_cypher_derive::FromRow;
@@ -0,0 +1,692 @@
+//! In-process stand-in for a neo4j server, to test code using `GraphClient` without one
+//!
+//! `FakeServer` serves the discovery document and the transactional endpoint over HTTP on a local
+//! port. Statements are answered with the response scripted for the first pattern they contain,
+//! or with an empty result, and are recorded with their parameters for later assertions.
+//!
+//! Transactions get a `Location` header and an expiry date like on a real server. They can be
+//! expired on demand to test the handling of `TransactionNotFound` errors, and a statement that
+//! fails rolls its transaction back.
+//!
+//! Like neo4j, the server builds the URLs it returns from the `Host` header, so it also works
+//! behind a proxy. Requests can be required to carry a header, to test authentication, and
+//! responses can be delayed, to test timeouts.
+//!
+//! This module requires the `testing` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[macro_use] extern crate serde_json;
+//! # extern crate rusted_cypher;
+//! # use rusted_cypher::{GraphClient, GraphError, Statement};
+//! # use rusted_cypher::testing::{FakeResponse, FakeServer};
+//! # fn main() { doctest().unwrap(); }
+//! # fn doctest() -> Result<(), GraphError> {
+//! let server = FakeServer::start()?;
+//! server.respond("MATCH (n:LANGUAGE)", FakeResponse::rows(&["n.name"], vec![
+//!     vec![json!("Rust")],
+//!     vec![json!("Haskell")],
+//! ]));
+//! server.respond("CREATE", FakeResponse::error(
+//!     "Neo.ClientError.Schema.ConstraintValidationFailed",
+//!     "Node already exists",
+//! ));
+//!
+//! let graph = GraphClient::connect(server.url())?;
+//!
+//! let result = graph.exec("MATCH (n:LANGUAGE) RETURN n.name")?;
+//! assert_eq!(result.data.len(), 2);
+//!
+//! let statement = Statement::new("CREATE (n:LANGUAGE {name: $name})").with_param("name", "Rust")?;
+//! assert!(graph.exec(statement).is_err());
+//!
+//! let received = server.received();
+//! assert_eq!(received[1].parameters["name"], "Rust");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use hyper::header::{Headers, Host, Location};
+use hyper::method::Method;
+use hyper::server::{Listening, Request, Response, Server};
+use hyper::status::StatusCode;
+use hyper::uri::RequestUri;
+use semver::Version;
+use serde_json::{self, json, Value};
+use time;
+
+use error::GraphError;
+
+const TRANSACTION_NOT_FOUND: &str = "Neo.ClientError.Transaction.TransactionNotFound";
+
+/// Response scripted for the statements matching a pattern
+#[derive(Clone, Debug, PartialEq)]
+pub struct FakeResponse {
+    result: Scripted,
+    stats: Option<Value>,
+    plan: Option<Value>,
+    delay: Option<Duration>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Scripted {
+    Rows { columns: Vec<String>, rows: Vec<Vec<Value>> },
+    Error { code: String, message: String },
+}
+
+impl FakeResponse {
+    /// A result with the given columns and rows
+    pub fn rows(columns: &[&str], rows: Vec<Vec<Value>>) -> Self {
+        FakeResponse {
+            result: Scripted::Rows {
+                columns: columns.iter().map(|&column| column.to_owned()).collect(),
+                rows,
+            },
+            stats: None,
+            plan: None,
+            delay: None,
+        }
+    }
+
+    /// A result without columns or rows, as for most updates
+    pub fn empty() -> Self {
+        FakeResponse::rows(&[], vec![])
+    }
+
+    /// An error with the given neo4j status code, as in `Neo.ClientError.Statement.SyntaxError`
+    pub fn error<C: Into<String>, M: Into<String>>(code: C, message: M) -> Self {
+        FakeResponse {
+            result: Scripted::Error {
+                code: code.into(),
+                message: message.into(),
+            },
+            stats: None,
+            plan: None,
+            delay: None,
+        }
+    }
+
+    /// Sets the counters returned to the statement when it is sent with `includeStats`, as in
+    /// `json!({"nodes_created": 1})`
+    ///
+    /// The counters that are not given are zero, and `contains_updates` is set unless given.
+    pub fn with_stats(mut self, stats: Value) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Sets the execution plan returned with the result, as for `EXPLAIN` and `PROFILE`
+    /// statements
+    ///
+    /// The plan is the root operator, as in `json!({"operatorType": "ProduceResults"})`.
+    pub fn with_plan(mut self, plan: Value) -> Self {
+        self.plan = Some(plan);
+        self
+    }
+
+    /// Waits before answering the request containing the statement
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// A request received by the `FakeServer`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceivedRequest {
+    pub method: Method,
+    pub path: String,
+    /// Number of statements in the body, `0` for the requests that only reset the timeout of a
+    /// transaction
+    pub statements: usize,
+}
+
+/// A statement received by the `FakeServer`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceivedStatement {
+    pub statement: String,
+    pub parameters: BTreeMap<String, Value>,
+    /// Database of the endpoint, on neo4j 4.0 and later
+    pub database: Option<String>,
+    /// Id of the transaction, `None` for the autocommit endpoint
+    pub transaction: Option<u64>,
+    /// Headers of the request, by name
+    pub headers: BTreeMap<String, String>,
+}
+
+struct State {
+    url: String,
+    version: Version,
+    rules: Vec<(String, FakeResponse)>,
+    required_headers: Vec<(String, String)>,
+    received: Vec<ReceivedStatement>,
+    requests: Vec<ReceivedRequest>,
+    clients: HashSet<SocketAddr>,
+    transactions: HashMap<u64, Instant>,
+    next_transaction: u64,
+    transaction_timeout: Duration,
+}
+
+/// Where a request to the transactional endpoint goes
+enum Target {
+    Begin,
+    Autocommit,
+    Run(u64),
+    Commit(u64),
+}
+
+/// A request, as read from hyper
+struct Received<'a> {
+    method: &'a Method,
+    path: &'a str,
+    headers: &'a Headers,
+    body: &'a str,
+    /// URL of the server, from the `Host` header
+    url: String,
+}
+
+/// The answer to a request, before it is written by hyper
+struct Answer {
+    status: StatusCode,
+    location: Option<String>,
+    body: Value,
+    delay: Option<Duration>,
+}
+
+impl Answer {
+    fn ok(body: Value) -> Self {
+        Answer { status: StatusCode::Ok, location: None, body, delay: None }
+    }
+
+    fn error(status: StatusCode, code: &str, message: &str) -> Self {
+        Answer {
+            status,
+            location: None,
+            body: json!({"results": [], "errors": [{"code": code, "message": message}]}),
+            delay: None,
+        }
+    }
+}
+
+impl State {
+    fn transaction_endpoint(&self, url: &str) -> String {
+        if self.version.major >= 4 {
+            format!("{}/db/{{databaseName}}/tx", url)
+        } else {
+            format!("{}/db/data/transaction", url)
+        }
+    }
+
+    fn transaction_url(&self, url: &str, database: Option<&str>, id: u64) -> String {
+        match database {
+            Some(database) => format!("{}/db/{}/tx/{}", url, database, id),
+            None => format!("{}/db/data/transaction/{}", url, id),
+        }
+    }
+
+    fn expires(&self) -> String {
+        let timeout = time::Duration::from_std(self.transaction_timeout)
+            .unwrap_or_else(|_| time::Duration::days(1));
+        (time::now_utc() + timeout).rfc822z().to_string()
+    }
+
+    fn handle(&mut self, request: &Received) -> Answer {
+        let statements = serde_json::from_str::<Value>(request.body).ok()
+            .and_then(|body| body["statements"].as_array().map(Vec::len))
+            .unwrap_or(0);
+        self.requests.push(ReceivedRequest {
+            method: request.method.clone(),
+            path: request.path.to_owned(),
+            statements,
+        });
+
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+
+        match (request.method, segments.as_slice()) {
+            (&Method::Get, [""]) if self.version.major >= 4 => Answer::ok(json!({
+                "transaction": self.transaction_endpoint(&request.url),
+                "neo4j_version": self.version.to_string(),
+                "neo4j_edition": "community",
+            })),
+            (&Method::Get, [""]) => Answer::ok(json!({
+                "data": format!("{}/db/data/", request.url),
+            })),
+            (&Method::Get, ["db", "data"]) if self.version.major < 4 => Answer::ok(json!({
+                "transaction": self.transaction_endpoint(&request.url),
+                "neo4j_version": self.version.to_string(),
+            })),
+            (_, ["db", _, "tx", ..]) | (_, ["db", "data", "transaction", ..])
+                if !self.is_authorized(request.headers) =>
+            {
+                Answer::error(StatusCode::Unauthorized, "Neo.ClientError.Security.Unauthorized",
+                              "Invalid credentials")
+            }
+            (_, ["db", database, "tx", rest @ ..]) if self.version.major >= 4 => {
+                let database = Some(database.to_string());
+                self.handle_transaction(request, database, rest)
+            }
+            (_, ["db", "data", "transaction", rest @ ..]) if self.version.major < 4 => {
+                self.handle_transaction(request, None, rest)
+            }
+            _ => Answer::error(StatusCode::NotFound, "Neo.ClientError.Request.Invalid",
+                               &format!("No endpoint at {}", request.path)),
+        }
+    }
+
+    fn is_authorized(&self, headers: &Headers) -> bool {
+        self.required_headers.iter().all(|(name, value)| {
+            headers.get_raw(name).is_some_and(|raw| raw.iter().any(|v| v == value.as_bytes()))
+        })
+    }
+
+    fn handle_transaction(&mut self, request: &Received, database: Option<String>, rest: &[&str])
+        -> Answer
+    {
+        let method = request.method;
+        let target = match (method, rest) {
+            (&Method::Post, []) => Target::Begin,
+            (&Method::Post, ["commit"]) => Target::Autocommit,
+            (&Method::Post, [id]) | (&Method::Delete, [id]) => match id.parse() {
+                Ok(id) => Target::Run(id),
+                Err(_) => return Answer::error(StatusCode::NotFound, TRANSACTION_NOT_FOUND,
+                                               "Invalid transaction id"),
+            },
+            (&Method::Post, [id, "commit"]) => match id.parse() {
+                Ok(id) => Target::Commit(id),
+                Err(_) => return Answer::error(StatusCode::NotFound, TRANSACTION_NOT_FOUND,
+                                               "Invalid transaction id"),
+            },
+            _ => return Answer::error(StatusCode::MethodNotAllowed,
+                                      "Neo.ClientError.Request.Invalid", "Invalid request"),
+        };
+
+        let id = match target {
+            Target::Begin => {
+                self.next_transaction += 1;
+                Some(self.next_transaction)
+            }
+            Target::Autocommit => None,
+            Target::Run(id) | Target::Commit(id) => {
+                match self.transactions.remove(&id) {
+                    Some(expires) if expires > Instant::now() => Some(id),
+                    _ => return Answer::error(StatusCode::NotFound, TRANSACTION_NOT_FOUND,
+                        "Unrecognized transaction id. Transaction may have timed out and been \
+                         rolled back."),
+                }
+            }
+        };
+
+        if *method == Method::Delete {
+            return Answer::ok(json!({"results": [], "errors": []}));
+        }
+
+        let (results, errors, delay) = self.run(request, database.clone(), id);
+
+        // a failed statement rolls the transaction back, and a commit closes it
+        let (id, status) = match (target, id) {
+            (Target::Begin, Some(id)) if errors.is_empty() => (id, StatusCode::Created),
+            (Target::Run(_), Some(id)) if errors.is_empty() => (id, StatusCode::Ok),
+            _ => {
+                let mut answer = Answer::ok(json!({"results": results, "errors": errors}));
+                answer.delay = delay;
+                return answer;
+            }
+        };
+
+        self.transactions.insert(id, Instant::now() + self.transaction_timeout);
+        let transaction = self.transaction_url(&request.url, database.as_deref(), id);
+
+        Answer {
+            status,
+            location: Some(transaction.clone()),
+            body: json!({
+                "commit": format!("{}/commit", transaction),
+                "transaction": {"expires": self.expires()},
+                "results": results,
+                "errors": errors,
+            }),
+            delay,
+        }
+    }
+
+    /// Answers the statements of the request body until one fails, returning the results, the
+    /// errors and the longest delay of the responses
+    fn run(&mut self, request: &Received, database: Option<String>, transaction: Option<u64>)
+        -> (Vec<Value>, Vec<Value>, Option<Duration>)
+    {
+        let statements = serde_json::from_str::<Value>(request.body).ok()
+            .and_then(|body| body["statements"].as_array().cloned())
+            .unwrap_or_default();
+        let headers = request.headers.iter()
+            .map(|header| (header.name().to_owned(), header.value_string()))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut results = Vec::new();
+        let mut delay = None;
+
+        for statement in statements {
+            let received = ReceivedStatement {
+                statement: statement["statement"].as_str().unwrap_or_default().to_owned(),
+                parameters: serde_json::from_value(statement["parameters"].clone())
+                    .unwrap_or_default(),
+                database: database.clone(),
+                transaction,
+                headers: headers.clone(),
+            };
+
+            let response = self.rules.iter()
+                .find(|(pattern, _)| received.statement.contains(pattern.as_str()))
+                .map_or_else(FakeResponse::empty, |(_, response)| response.clone());
+            self.received.push(received);
+            delay = ::std::cmp::max(delay, response.delay);
+
+            match response.result {
+                Scripted::Rows { columns, rows } => {
+                    let mut result = json!({
+                        "columns": columns,
+                        "data": rows.into_iter()
+                            .map(|row| json!({"row": row, "meta": []}))
+                            .collect::<Vec<_>>(),
+                    });
+                    if statement["includeStats"] == json!(true) {
+                        result["stats"] = stats(response.stats);
+                    }
+                    if let Some(plan) = response.plan {
+                        result["plan"] = json!({"root": plan});
+                    }
+                    results.push(result);
+                }
+                Scripted::Error { code, message } => {
+                    return (results, vec![json!({"code": code, "message": message})], delay);
+                }
+            }
+        }
+
+        (results, vec![], delay)
+    }
+}
+
+/// Completes the scripted counters of a statement
+fn stats(scripted: Option<Value>) -> Value {
+    let counters = [
+        "nodes_created", "nodes_deleted", "properties_set", "relationships_created",
+        "relationship_deleted", "labels_added", "labels_removed", "indexes_added",
+        "indexes_removed", "constraints_added", "constraints_removed",
+    ];
+    let mut stats: serde_json::Map<String, Value> = counters.iter()
+        .map(|&counter| (counter.to_owned(), json!(0)))
+        .collect();
+    if let Some(Value::Object(scripted)) = scripted {
+        stats.extend(scripted);
+    }
+
+    if !stats.contains_key("contains_updates") {
+        let updates = counters.iter().any(|&counter| stats[counter] != json!(0));
+        stats.insert("contains_updates".to_owned(), json!(updates));
+    }
+    Value::Object(stats)
+}
+
+/// A local HTTP server standing in for neo4j
+///
+/// The server stops answering when it is dropped.
+pub struct FakeServer {
+    listening: Listening,
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeServer {
+    /// Starts a server standing in for neo4j 4.4
+    pub fn start() -> Result<Self, GraphError> {
+        FakeServer::start_with_version("4.4.0")
+    }
+
+    /// Starts a server standing in for the given version of neo4j
+    ///
+    /// Before neo4j 4.0, the discovery document links to the `/db/data/` service root and the
+    /// transactional endpoint is `/db/data/transaction`.
+    pub fn start_with_version(version: &str) -> Result<Self, GraphError> {
+        let state = Arc::new(Mutex::new(State {
+            url: String::new(),
+            version: Version::parse(version)?,
+            rules: Vec::new(),
+            required_headers: Vec::new(),
+            received: Vec::new(),
+            requests: Vec::new(),
+            clients: HashSet::new(),
+            transactions: HashMap::new(),
+            next_transaction: 0,
+            transaction_timeout: Duration::from_secs(60),
+        }));
+
+        let handler_state = state.clone();
+        let listening = Server::http("127.0.0.1:0")?
+            .handle_threads(move |mut req: Request, mut res: Response| {
+                // requests sent through a proxy have an absolute URI
+                let path = match req.uri {
+                    RequestUri::AbsoluteUri(ref url) => url.path().to_owned(),
+                    ref uri => uri.to_string(),
+                };
+                let mut body = String::new();
+                if let Err(e) = req.read_to_string(&mut body) {
+                    warn!("Unable to read the request to {}: {}", path, e);
+                }
+
+                let answer = {
+                    let mut state = lock(&handler_state);
+                    state.clients.insert(req.remote_addr);
+
+                    let url = match req.headers.get::<Host>() {
+                        Some(host) => {
+                            format!("http://{}:{}", host.hostname, host.port.unwrap_or(80))
+                        }
+                        None => state.url.clone(),
+                    };
+                    state.handle(&Received {
+                        method: &req.method,
+                        path: &path,
+                        headers: &req.headers,
+                        body: &body,
+                        url,
+                    })
+                };
+
+                if let Some(delay) = answer.delay {
+                    thread::sleep(delay);
+                }
+
+                *res.status_mut() = answer.status;
+                if let Some(location) = answer.location {
+                    res.headers_mut().set(Location(location));
+                }
+                if let Err(e) = res.send(answer.body.to_string().as_bytes()) {
+                    warn!("Unable to answer the request to {}: {}", path, e);
+                }
+            }, 4)?;
+
+        let url = format!("http://{}", listening.socket);
+        lock(&state).url = url.clone();
+
+        Ok(FakeServer { listening, url, state })
+    }
+
+    /// Returns the URL of the server, to give to `GraphClient::connect`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Answers the statements containing the pattern with the given response
+    ///
+    /// Patterns are tried in the order they were added. Statements matching none are answered
+    /// with an empty result.
+    pub fn respond<P: Into<String>>(&self, pattern: P, response: FakeResponse) {
+        lock(&self.state).rules.push((pattern.into(), response));
+    }
+
+    /// Rejects the requests to the transactional endpoint without the given header, as neo4j does
+    /// for invalid credentials
+    ///
+    /// When several headers are required, requests must carry all of them.
+    pub fn require_header<N: Into<String>, V: Into<String>>(&self, name: N, value: V) {
+        lock(&self.state).required_headers.push((name.into(), value.into()));
+    }
+
+    /// Returns the statements received so far, in order
+    pub fn received(&self) -> Vec<ReceivedStatement> {
+        lock(&self.state).received.clone()
+    }
+
+    /// Returns the requests received so far, in order
+    ///
+    /// Unlike `FakeServer::received`, this includes the requests without statements, as the ones
+    /// rolling back a transaction or resetting its timeout.
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        lock(&self.state).requests.clone()
+    }
+
+    /// Forgets the statements and requests received so far
+    pub fn clear_received(&self) {
+        let mut state = lock(&self.state);
+        state.received.clear();
+        state.requests.clear();
+    }
+
+    /// Sets how long transactions stay open without a request, 60 seconds by default
+    pub fn set_transaction_timeout(&self, timeout: Duration) {
+        lock(&self.state).transaction_timeout = timeout;
+    }
+
+    /// Expires all open transactions, as if their timeout had passed
+    ///
+    /// Further requests to them fail with `Neo.ClientError.Transaction.TransactionNotFound`.
+    pub fn expire_transactions(&self) {
+        lock(&self.state).transactions.clear();
+    }
+
+    /// Returns the number of transactions that are open
+    pub fn open_transactions(&self) -> usize {
+        let state = lock(&self.state);
+        let now = Instant::now();
+        state.transactions.values().filter(|&&expires| expires > now).count()
+    }
+
+    /// Returns the number of connections that sent requests, told apart by their client address
+    pub fn connections(&self) -> usize {
+        lock(&self.state).clients.len()
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        // without it, dropping `Listening` waits for the server thread, which never ends
+        let _ = self.listening.close();
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    match state.lock() {
+        Ok(state) => state,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::{GraphClient, Statement};
+
+    #[test]
+    fn script_responses() {
+        let server = FakeServer::start().unwrap();
+        server.respond("RETURN $name", FakeResponse::rows(&["name"], vec![vec![json!("Neo")]]));
+        server.respond("FAIL", FakeResponse::error("Neo.ClientError.Statement.SyntaxError",
+                                                   "Invalid input"));
+
+        let graph = GraphClient::connect(server.url()).unwrap().with_database("movies");
+        let statement = Statement::new("RETURN $name AS name").with_param("name", "Neo").unwrap();
+
+        let result = graph.exec(statement).unwrap();
+        assert_eq!(result.rows().next().unwrap().get::<String>("name").unwrap(), "Neo");
+        assert!(graph.exec("MATCH (n) RETURN n").unwrap().data.is_empty());
+
+        match graph.exec("FAIL") {
+            Err(GraphError::Neo4j(errors)) => assert_eq!(errors[0].message, "Invalid input"),
+            other => panic!("Unexpected result: {:?}", other.map(|result| result.columns)),
+        }
+
+        let received = server.received();
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[0].statement, "RETURN $name AS name");
+        assert_eq!(received[0].parameters["name"], "Neo");
+        assert_eq!(received[0].database.as_deref(), Some("movies"));
+        assert_eq!(received[0].transaction, None);
+
+        server.clear_received();
+        assert!(server.received().is_empty());
+    }
+
+    #[test]
+    fn transactions() {
+        let server = FakeServer::start_with_version("3.5.0").unwrap();
+        let graph = GraphClient::connect(server.url()).unwrap();
+        assert_eq!(graph.neo4j_version().major, 3);
+
+        let (mut transaction, _) = graph.transaction()
+            .with_statement("CREATE (n:FAKE)")
+            .begin()
+            .unwrap();
+        assert_eq!(server.open_transactions(), 1);
+
+        transaction.exec("MATCH (n:FAKE) RETURN n").unwrap();
+        transaction.commit().unwrap();
+        assert_eq!(server.open_transactions(), 0);
+
+        let (transaction, _) = graph.transaction().begin().unwrap();
+        transaction.rollback().unwrap();
+        assert_eq!(server.open_transactions(), 0);
+
+        let received = server.received();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].transaction, Some(1));
+        assert_eq!(received[1].transaction, Some(1));
+        assert_eq!(received[1].database, None);
+    }
+
+    #[test]
+    fn expire_and_roll_back() {
+        let server = FakeServer::start().unwrap();
+        server.respond("FAIL", FakeResponse::error("Neo.ClientError.Statement.SyntaxError",
+                                                   "Invalid input"));
+        let graph = GraphClient::connect(server.url()).unwrap();
+
+        fn is_not_found<T>(result: Result<T, GraphError>) -> bool {
+            match result {
+                Err(GraphError::Neo4j(errors)) => errors[0].code == TRANSACTION_NOT_FOUND,
+                _ => false,
+            }
+        }
+
+        let (mut transaction, _) = graph.transaction().begin().unwrap();
+        server.expire_transactions();
+        assert!(is_not_found(transaction.exec("RETURN 1")));
+
+        server.set_transaction_timeout(Duration::from_secs(0));
+        let (mut transaction, _) = graph.transaction().begin().unwrap();
+        assert_eq!(server.open_transactions(), 0);
+        assert!(is_not_found(transaction.exec("RETURN 1")));
+
+        server.set_transaction_timeout(Duration::from_secs(60));
+        let (mut transaction, _) = graph.transaction().begin().unwrap();
+        assert!(transaction.exec("FAIL").is_err());
+        assert_eq!(server.open_transactions(), 0);
+        assert!(is_not_found(transaction.commit()));
+    }
+}
@@ -0,0 +1,104 @@
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde_json;
+extern crate rusted_cypher;
+
+use rusted_cypher::{FromRow, GraphError};
+use rusted_cypher::cypher::CypherResult;
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Language {
+    #[cypher(rename = "n.name")]
+    name: String,
+    #[cypher(rename = "n.level")]
+    level: Option<String>,
+    #[cypher(rename = "n.safe", default)]
+    safe: bool,
+    #[cypher(default = "default_year")]
+    year: u32,
+    #[cypher(flatten)]
+    creator: Creator,
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Creator {
+    creator: String,
+    company: Option<Company>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Company {
+    name: String,
+}
+
+#[derive(Debug, FromRow)]
+struct Required {
+    #[cypher(rename = "n.name")]
+    #[allow(dead_code)]
+    name: String,
+}
+
+fn default_year() -> u32 {
+    2000
+}
+
+fn make_result(json: &str) -> CypherResult {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn rows_as() {
+    let result = make_result(r#"{
+        "columns": ["n.name", "n.level", "n.safe", "year", "creator", "company"],
+        "data": [
+            {"row": ["Rust", "low", true, 2010, "Graydon Hoare", {"name": "Mozilla"}]},
+            {"row": ["Python", null, null, null, "Guido van Rossum", null]}
+        ]
+    }"#);
+
+    let languages = result.rows_as::<Language>().collect::<Result<Vec<_>, _>>().unwrap();
+
+    assert_eq!(languages[0], Language {
+        name: "Rust".to_owned(),
+        level: Some("low".to_owned()),
+        safe: true,
+        year: 2010,
+        creator: Creator {
+            creator: "Graydon Hoare".to_owned(),
+            company: Some(Company { name: "Mozilla".to_owned() }),
+        },
+    });
+
+    assert_eq!(languages[1], Language {
+        name: "Python".to_owned(),
+        level: None,
+        safe: false,
+        year: 2000,
+        creator: Creator {
+            creator: "Guido van Rossum".to_owned(),
+            company: None,
+        },
+    });
+}
+
+#[test]
+fn missing_columns() {
+    let result = make_result(r#"{
+        "columns": ["n.name", "creator"],
+        "data": [{"row": ["C", "Dennis Ritchie"]}]
+    }"#);
+
+    let language = result.rows_as::<Language>().next().unwrap().unwrap();
+    assert_eq!(language.level, None);
+    assert!(!language.safe);
+    assert_eq!(language.year, 2000);
+    assert_eq!(language.creator.company, None);
+
+    let result = make_result(r#"{"columns": ["n.level"], "data": [{"row": ["low"]}]}"#);
+
+    match result.rows_as::<Required>().next().unwrap() {
+        Err(GraphError::Statement(message)) => assert_eq!(message, "No such column: n.name"),
+        other => panic!("Expected missing column error, got {:?}", other),
+    }
+}
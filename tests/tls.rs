@@ -0,0 +1,167 @@
+extern crate rusted_cypher;
+extern crate rustls;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread;
+
+use rustls::{RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+use rustls::crypto::ring;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::pki_types::pem::PemObject;
+use rustls::server::WebPkiClientVerifier;
+
+use rusted_cypher::{GraphClient, GraphError};
+use rusted_cypher::net::TlsConfig;
+
+/// A certificate for `localhost` from `tests/certificates`, valid until 2126
+///
+/// `localhost` and `client` are signed by `ca`, `self_signed` is signed by itself.
+struct Certificate {
+    pem: &'static [u8],
+    key_pem: &'static [u8],
+}
+
+const CA: &[u8] = include_bytes!("certificates/ca.pem");
+
+const LOCALHOST: Certificate = Certificate {
+    pem: include_bytes!("certificates/localhost.pem"),
+    key_pem: include_bytes!("certificates/localhost.key"),
+};
+
+const SELF_SIGNED: Certificate = Certificate {
+    pem: include_bytes!("certificates/self_signed.pem"),
+    key_pem: include_bytes!("certificates/self_signed.key"),
+};
+
+const CLIENT: Certificate = Certificate {
+    pem: include_bytes!("certificates/client.pem"),
+    key_pem: include_bytes!("certificates/client.key"),
+};
+
+/// Starts a neo4j 4 stand-in server on `localhost`, answering every statement with `1`
+///
+/// If `client_ca` is given, clients must present a certificate it signed.
+fn start_server(server: &Certificate, client_ca: Option<&[u8]>) -> u16 {
+    let provider = Arc::new(ring::default_provider());
+    let config = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .unwrap();
+
+    let config = match client_ca {
+        Some(ca) => {
+            let mut roots = RootCertStore::empty();
+            roots.add(CertificateDer::from_pem_slice(ca).unwrap()).unwrap();
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .unwrap();
+            config.with_client_cert_verifier(verifier)
+        }
+        None => config.with_no_client_auth(),
+    };
+
+    let certificate = CertificateDer::from_pem_slice(server.pem).unwrap();
+    let key = PrivateKeyDer::from_pem_slice(server.key_pem).unwrap();
+    let config = Arc::new(config.with_single_cert(vec![certificate], key).unwrap());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let config = config.clone();
+            thread::spawn(move || {
+                let connection = ServerConnection::new(config).unwrap();
+                let mut reader = BufReader::new(StreamOwned::new(connection, stream.unwrap()));
+
+                // the handshake happens on the first read, and fails for rejected clients
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    return;
+                }
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if line.to_lowercase().starts_with("content-length:") {
+                        content_length = line[15..].trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let response = if request_line.starts_with("GET / ") {
+                    format!("{{\"transaction\": \"https://localhost:{}/db/{{databaseName}}/tx\", \
+                             \"neo4j_version\": \"4.4.0\"}}", port)
+                } else {
+                    r#"{"results": [{"columns": ["n"], "data": [{"row": [1]}]}], "errors": []}"#
+                        .to_owned()
+                };
+
+                let stream = reader.get_mut();
+                write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                                Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                       response.len(), response).unwrap();
+                stream.conn.send_close_notify();
+                stream.flush().ok();
+            });
+        }
+    });
+
+    port
+}
+
+fn exec(port: u16, tls: TlsConfig) -> Result<i32, GraphError> {
+    let graph = GraphClient::builder(format!("https://localhost:{}", port))
+        .with_tls(tls)
+        .connect()?;
+
+    let result = graph.exec("RETURN 1 AS n")?;
+    let n = result.rows().next().unwrap().get("n")?;
+    Ok(n)
+}
+
+#[test]
+fn custom_root_certificate() {
+    let port = start_server(&LOCALHOST, None);
+
+    let tls = TlsConfig::new().with_root_certificate(CA).unwrap();
+    assert_eq!(exec(port, tls).unwrap(), 1);
+
+    assert!(exec(port, TlsConfig::new()).is_err());
+}
+
+#[test]
+fn self_signed_certificate() {
+    let port = start_server(&SELF_SIGNED, None);
+
+    let tls = TlsConfig::new().with_root_certificate(SELF_SIGNED.pem).unwrap();
+    assert_eq!(exec(port, tls).unwrap(), 1);
+
+    assert!(exec(port, TlsConfig::new()).is_err());
+}
+
+#[test]
+fn accept_invalid_certs() {
+    let port = start_server(&SELF_SIGNED, None);
+
+    assert_eq!(exec(port, TlsConfig::new().danger_accept_invalid_certs(true)).unwrap(), 1);
+    assert!(exec(port, TlsConfig::new().danger_accept_invalid_certs(false)).is_err());
+}
+
+#[test]
+fn client_certificate() {
+    let port = start_server(&LOCALHOST, Some(CA));
+
+    let tls = TlsConfig::new()
+        .with_root_certificate(CA).unwrap()
+        .with_client_certificate(CLIENT.pem, CLIENT.key_pem).unwrap();
+    assert_eq!(exec(port, tls).unwrap(), 1);
+
+    let tls = TlsConfig::new().with_root_certificate(CA).unwrap();
+    assert!(exec(port, tls).is_err());
+}